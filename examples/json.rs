@@ -4,6 +4,7 @@ use std::{
 };
 
 use mini_parc::{
+    json::{Dialect, Value},
     parsers::{
         and_p::KeepFirstOutputOnly, string_p::string_parser, ParseMatch, ParseWhile,
         ParseWhileOrNothing,
@@ -12,12 +13,12 @@ use mini_parc::{
 };
 
 fn parse_integer() -> impl Parser<Output = String> {
-    ParseWhile(|c| c.is_numeric())
+    ParseWhile(|c| c.is_ascii_digit())
 }
 
 fn parse_float() -> impl Parser<Output = String> {
-    let p_whole = ParseWhile(|c| c.is_numeric());
-    let p_decimal = ParseWhileOrNothing(|c| c.is_numeric());
+    let p_whole = ParseWhile(|c| c.is_ascii_digit());
+    let p_decimal = ParseWhileOrNothing(|c| c.is_ascii_digit());
 
     // Parse the whole part
     p_whole
@@ -41,24 +42,42 @@ pub enum Primitives {
 }
 
 fn parse_number() -> impl Parser<Output = Primitives> {
-    parse_float().otherwise(parse_integer()).with_mapping(&|s| {
-        let numb = s.parse::<f64>().unwrap();
-        Primitives::Number(numb)
-    })
+    // Only ASCII digits are parsed, so this should not fail, but a number that can not be
+    // represented is a parsing error rather than a panic
+    parse_float()
+        .otherwise(parse_integer())
+        .with_try_mapping(&|s| s.parse::<f64>().ok().map(Primitives::Number))
 }
 
 fn parse_string() -> impl Parser<Output = Primitives> {
     mini_parc::parsers::string_p::string_parser().with_mapping(&|s| Primitives::String(s))
 }
 
-pub fn primitive_parser() -> impl Parser<Output = Primitives> {
+fn parse_scalar() -> impl Parser<Output = Primitives> {
     ParseMatch("true")
         .with_mapping(&|_| Primitives::True)
         .otherwise(ParseMatch("false").with_mapping(&|_| Primitives::False))
         .otherwise(parse_number())
         .otherwise(parse_string())
-        .otherwise(ArrayParser)
-        .otherwise(ObjectParser)
+}
+
+pub fn primitive_parser() -> impl Parser<Output = Primitives> {
+    ValueParser.label("value")
+}
+
+/// Arrays and objects are chosen by their first character, rather than tried one after
+/// the other, so that an error inside of them (and its context) is not thrown away
+struct ValueParser;
+
+impl Parser for ValueParser {
+    type Output = Primitives;
+    fn parse(&self, input: &str) -> mini_parc::type_alias::ParserRes<Self::Output> {
+        match input.chars().next() {
+            Some('[') => ArrayParser.parse(input),
+            Some('{') => ObjectParser.parse(input),
+            _ => parse_scalar().parse(input),
+        }
+    }
 }
 
 struct ArrayParser;
@@ -67,20 +86,22 @@ impl Parser for ArrayParser {
     type Output = Primitives;
     fn parse(&self, input: &str) -> mini_parc::type_alias::ParserRes<Self::Output> {
         let whitespace_p = ParseWhileOrNothing(|c| c.is_whitespace());
-        let (_, mut inp) = ParseMatch('[').parse(input)?;
+        let (_, rest) = ParseMatch('[').parse(input)?;
+        let (_, mut inp) = whitespace_p.parse(&rest)?;
         let mut acc = vec![];
-        loop {
-            let (_, rest) = whitespace_p.parse(&inp).unwrap();
-            let Ok((prim, rest)) = primitive_parser().parse(&rest) else {
-                break;
-            };
-            inp = rest;
+        let mut expect_element = !inp.starts_with(']');
+        while expect_element {
+            let (prim, rest) = primitive_parser().context("in array element").parse(&inp)?;
             acc.push(prim);
-            let (_, rest) = whitespace_p.parse(&inp).unwrap();
-            let Ok((_, rest)) = ParseMatch(',').parse(&rest) else {
-                break;
-            };
+            let (_, rest) = whitespace_p.parse(&rest)?;
             inp = rest;
+            // An element is expected after every comma
+            if let Ok((_, rest)) = ParseMatch(',').parse(&inp) {
+                let (_, rest) = whitespace_p.parse(&rest)?;
+                inp = rest;
+            } else {
+                expect_element = false;
+            }
         }
         // Parse closing bracket
         let (_, rest) = ParseMatch(']').parse(&inp)?;
@@ -98,17 +119,19 @@ impl Parser for ObjectParser {
         let mut map: HashMap<String, Primitives> = HashMap::new();
 
         loop {
-            let (_, rest) = whitespace_p.parse(&inp).unwrap();
+            let (_, rest) = whitespace_p.parse(&inp)?;
             let Ok((string, rest)) = string_parser().parse(&rest) else {
                 break;
             };
 
-            let (_, rest) = whitespace_p.parse(&rest).unwrap();
+            let (_, rest) = whitespace_p.parse(&rest)?;
             let (_, rest) = ParseMatch(':').parse(&rest)?;
-            let (_, rest) = whitespace_p.parse(&rest).unwrap();
+            let (_, rest) = whitespace_p.parse(&rest)?;
 
-            let (prim, rest) = primitive_parser().parse(&rest)?;
-            let (_, rest) = whitespace_p.parse(&rest).unwrap();
+            let (prim, rest) = primitive_parser()
+                .context(format!("in object value for key {string:?}"))
+                .parse(&rest)?;
+            let (_, rest) = whitespace_p.parse(&rest)?;
             map.insert(string, prim);
             inp = rest;
 
@@ -123,13 +146,82 @@ impl Parser for ObjectParser {
     }
 }
 
+/// A setting such as `size = {width: 2}`, whose value is read by the `json` module. Its
+/// parsers are made of the same combinators, so they can be combined with any other parser.
+fn setting() -> impl Parser<Output = (String, Value)> {
+    let spaces = || ParseWhileOrNothing(|c| c == ' ');
+    ParseWhile(|c| c.is_alphanumeric() || c == '_')
+        .and_then(spaces())
+        .combine(KeepFirstOutputOnly)
+        .and_then(ParseMatch('='))
+        .combine(KeepFirstOutputOnly)
+        .and_then(spaces())
+        .combine(KeepFirstOutputOnly)
+        .and_then(Dialect::JSON5.parser())
+}
+
 fn main() {
-    println!("Please enter a single json line to parse it:");
+    println!("Please enter a single json line (or a `name = json5` setting) to parse it:");
     let _ = stdout().flush();
     let mut buffer: String = String::new();
     stdin()
         .read_line(&mut buffer)
         .expect("Error reading user input");
-    let par = primitive_parser();
-    println!("{:?}", par.parse(&buffer));
+    let line = buffer.trim_end();
+    match setting().parse_complete(line) {
+        Ok((name, value)) => println!("{name} = {value:?}"),
+        Err(_) => println!("{:?}", primitive_parser().parse_complete(line)),
+    }
+}
+
+#[cfg(test)]
+mod json_test {
+    use super::{primitive_parser, setting, Primitives};
+    use mini_parc::{json::Value, traits::Parser};
+
+    #[test]
+    fn numbers() {
+        let p = primitive_parser();
+        assert_eq!(
+            p.parse_complete("[1, 2.5]"),
+            Ok(Primitives::Array(vec![
+                Primitives::Number(1.0),
+                Primitives::Number(2.5)
+            ]))
+        );
+        // Non ASCII digits used to be parsed as a number, which then failed to convert
+        assert!(p.parse_complete("\u{0661}").is_err());
+        assert!(p.parse_complete("{\"a\": \"\\").is_err());
+    }
+
+    #[test]
+    fn nested_error() {
+        let p = primitive_parser();
+        assert_eq!(p.parse_complete("[]"), Ok(Primitives::Array(vec![])));
+
+        let err = p.parse_complete("{\"a\": [1, x]}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected value, in array element, in object value for key \"a\""
+        );
+    }
+
+    #[test]
+    fn settings() {
+        assert_eq!(
+            setting().parse_complete("size = {width: 2}"),
+            Ok((
+                "size".to_string(),
+                Value::Object(vec![("width".to_string(), Value::Number(2.0))])
+            ))
+        );
+        // The error of the `json` module knows where it happened
+        assert_eq!(
+            setting()
+                .parse_complete("size = [1, x]")
+                .unwrap_err()
+                .to_string(),
+            "expected a value at line 1, column 12, in array element"
+        );
+    }
 }
@@ -0,0 +1,498 @@
+//! The `grammar!` macro: PEG rules written inline, expanded into `mini_parc` combinators.
+
+use std::collections::{HashMap, HashSet};
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    braced, parenthesized,
+    parse::{Parse, ParseStream},
+    Expr, Ident, LitStr, Token, Type, Visibility,
+};
+
+/// Rules that can be used without being defined
+const BUILTINS: [&str; 6] = ["any", "digit", "alpha", "alnum", "ws", "eof"];
+
+pub struct Grammar {
+    rules: Vec<RuleDef>,
+}
+
+struct RuleDef {
+    vis: Visibility,
+    name: Ident,
+    ty: Option<Type>,
+    body: Choice,
+}
+
+struct Choice {
+    alternatives: Vec<Sequence>,
+}
+
+struct Sequence {
+    items: Vec<Item>,
+    /// The statements of the action block
+    action: Option<TokenStream2>,
+}
+
+struct Item {
+    capture: Option<Ident>,
+    prefix: Option<Prefix>,
+    atom: Atom,
+    postfix: Option<Postfix>,
+}
+
+enum Prefix {
+    Peek,
+    Not,
+}
+
+enum Postfix {
+    Many,
+    Many1,
+    Optional,
+}
+
+enum Atom {
+    Literal(LitStr),
+    Rule(Ident),
+    Group(Choice),
+    Rust(Expr),
+}
+
+impl Parse for Grammar {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut rules = vec![];
+        while !input.is_empty() {
+            rules.push(input.parse()?);
+        }
+        Ok(Self { rules })
+    }
+}
+
+impl Parse for RuleDef {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        let name = input.parse()?;
+        let ty = if input.peek(Token![:]) {
+            input.parse::<Token![:]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        input.parse::<Token![=]>()?;
+        let body = input.parse()?;
+        input.parse::<Token![;]>()?;
+        Ok(Self {
+            vis,
+            name,
+            ty,
+            body,
+        })
+    }
+}
+
+impl Parse for Choice {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut alternatives = vec![input.parse()?];
+        while input.peek(Token![|]) {
+            input.parse::<Token![|]>()?;
+            alternatives.push(input.parse()?);
+        }
+        Ok(Self { alternatives })
+    }
+}
+
+impl Parse for Sequence {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut items = vec![];
+        while !(input.is_empty()
+            || input.peek(Token![|])
+            || input.peek(Token![;])
+            || input.peek(Token![=>]))
+        {
+            items.push(input.parse()?);
+        }
+        if items.is_empty() {
+            return Err(input.error("expected at least one item"));
+        }
+
+        let action = if input.peek(Token![=>]) {
+            input.parse::<Token![=>]>()?;
+            let content;
+            braced!(content in input);
+            Some(content.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { items, action })
+    }
+}
+
+impl Parse for Item {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let capture = if input.peek(Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+            let name = input.parse()?;
+            input.parse::<Token![:]>()?;
+            Some(name)
+        } else {
+            None
+        };
+
+        let prefix = if input.peek(Token![&]) {
+            input.parse::<Token![&]>()?;
+            Some(Prefix::Peek)
+        } else if input.peek(Token![!]) {
+            input.parse::<Token![!]>()?;
+            Some(Prefix::Not)
+        } else {
+            None
+        };
+
+        let atom = if input.peek(LitStr) {
+            Atom::Literal(input.parse()?)
+        } else if input.peek(Ident) {
+            Atom::Rule(input.parse()?)
+        } else if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            Atom::Group(content.parse()?)
+        } else if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+            Atom::Rust(content.parse()?)
+        } else {
+            return Err(input.error("expected a literal, a rule, a group or a {parser}"));
+        };
+
+        let postfix = if input.peek(Token![*]) {
+            input.parse::<Token![*]>()?;
+            Some(Postfix::Many)
+        } else if input.peek(Token![+]) {
+            input.parse::<Token![+]>()?;
+            Some(Postfix::Many1)
+        } else if input.peek(Token![?]) {
+            input.parse::<Token![?]>()?;
+            Some(Postfix::Optional)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            capture,
+            prefix,
+            atom,
+            postfix,
+        })
+    }
+}
+
+impl Choice {
+    /// Whether some alternative can succeed without consuming anything
+    fn nullable(&self, nullable_rules: &HashSet<String>) -> bool {
+        self.alternatives
+            .iter()
+            .any(|seq| seq.items.iter().all(|item| item.nullable(nullable_rules)))
+    }
+}
+
+impl Item {
+    /// Whether this item can succeed without consuming anything, given the rules that can
+    fn nullable(&self, nullable_rules: &HashSet<String>) -> bool {
+        self.prefix.is_some()
+            || matches!(self.postfix, Some(Postfix::Many | Postfix::Optional))
+            || match &self.atom {
+                Atom::Literal(lit) => lit.value().is_empty(),
+                Atom::Rule(name) => {
+                    name == "ws" || name == "eof" || nullable_rules.contains(&name.to_string())
+                }
+                Atom::Group(group) => group.nullable(nullable_rules),
+                Atom::Rust(_) => false,
+            }
+    }
+
+    /// Where errors about this item are reported
+    fn atom_span(&self) -> Span {
+        match &self.atom {
+            Atom::Literal(lit) => lit.span(),
+            Atom::Rule(name) => name.span(),
+            Atom::Group(_) | Atom::Rust(_) => Span::call_site(),
+        }
+    }
+}
+
+/// Rules that can be reached from the choice without consuming any input
+fn leftmost_rules(choice: &Choice, nullable_rules: &HashSet<String>, out: &mut HashSet<String>) {
+    for seq in &choice.alternatives {
+        for item in &seq.items {
+            match &item.atom {
+                Atom::Rule(name) => {
+                    out.insert(name.to_string());
+                }
+                Atom::Group(group) => leftmost_rules(group, nullable_rules, out),
+                Atom::Literal(_) | Atom::Rust(_) => {}
+            }
+            if !item.nullable(nullable_rules) {
+                break;
+            }
+        }
+    }
+}
+
+/// Rules that may call themselves at the same position
+fn left_recursive_rules(grammar: &Grammar) -> HashSet<String> {
+    // Rules that may match nothing, found by iterating until nothing changes
+    let mut nullable = HashSet::new();
+    loop {
+        let before = nullable.len();
+        for rule in &grammar.rules {
+            if rule.body.nullable(&nullable) {
+                nullable.insert(rule.name.to_string());
+            }
+        }
+        if nullable.len() == before {
+            break;
+        }
+    }
+
+    let leftmost: HashMap<String, HashSet<String>> = grammar
+        .rules
+        .iter()
+        .map(|r| {
+            let mut out = HashSet::new();
+            leftmost_rules(&r.body, &nullable, &mut out);
+            (r.name.to_string(), out)
+        })
+        .collect();
+
+    let mut recursive = HashSet::new();
+    for rule in leftmost.keys() {
+        let mut seen = HashSet::new();
+        let mut stack: Vec<&String> = leftmost[rule].iter().collect();
+        while let Some(next) = stack.pop() {
+            if next == rule {
+                recursive.insert(rule.clone());
+                break;
+            }
+            if seen.insert(next) {
+                stack.extend(leftmost.get(next).into_iter().flatten());
+            }
+        }
+    }
+    recursive
+}
+
+struct Codegen<'g> {
+    rules: HashSet<String>,
+    grammar: &'g Grammar,
+}
+
+impl<'g> Codegen<'g> {
+    fn choice(&self, choice: &Choice) -> syn::Result<TokenStream2> {
+        let has_action = choice.alternatives.iter().any(|s| s.action.is_some());
+        let alternatives = choice
+            .alternatives
+            .iter()
+            .map(|seq| self.sequence(seq))
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        let (first, others) = alternatives.split_first().expect("choice is never empty");
+        if others.is_empty() {
+            return Ok(first.clone());
+        }
+        if has_action {
+            return Ok(others
+                .iter()
+                .fold(first.clone(), |acc, alt| quote! { #acc.otherwise(#alt) }));
+        }
+
+        // Without actions, the alternatives may have different outputs, so the matched text
+        // is used instead
+        let chain = others.iter().fold(
+            quote! { #first.value(()) },
+            |acc, alt| quote! { #acc.otherwise(#alt.value(())) },
+        );
+        Ok(quote! { #chain.recognize() })
+    }
+
+    fn sequence(&self, seq: &Sequence) -> syn::Result<TokenStream2> {
+        let items = seq
+            .items
+            .iter()
+            .map(|item| self.item(item))
+            .collect::<syn::Result<Vec<_>>>()?;
+        let (first, others) = items.split_first().expect("sequence is never empty");
+        let parser = others
+            .iter()
+            .fold(first.clone(), |acc, item| quote! { #acc.and_then(#item) });
+
+        let Some(action) = &seq.action else {
+            return Ok(parser);
+        };
+        let capture = |item: &Item| match &item.capture {
+            Some(name) => quote! { #name },
+            None => quote! { _ },
+        };
+        let pattern = seq.items[1..]
+            .iter()
+            .fold(capture(&seq.items[0]), |acc, item| {
+                let next = capture(item);
+                quote! { (#acc, #next) }
+            });
+        Ok(quote! { #parser.with_mapping(&|#pattern| { #action }) })
+    }
+
+    fn item(&self, item: &Item) -> syn::Result<TokenStream2> {
+        let atom = self.atom(&item.atom)?;
+        let atom = match item.prefix {
+            Some(Prefix::Peek) => quote! { ::mini_parc::parsers::look_p::peek(#atom) },
+            Some(Prefix::Not) => quote! { ::mini_parc::parsers::look_p::not(#atom) },
+            None => atom,
+        };
+        Ok(match item.postfix {
+            Some(Postfix::Many) => {
+                quote! { ::mini_parc::parsers::repeat_p::RepeatParser::new(#atom).or_default() }
+            }
+            Some(Postfix::Many1) => {
+                quote! { ::mini_parc::parsers::repeat_p::RepeatParser::new(#atom) }
+            }
+            Some(Postfix::Optional) => quote! { #atom.optional() },
+            None => atom,
+        })
+    }
+
+    fn atom(&self, atom: &Atom) -> syn::Result<TokenStream2> {
+        Ok(match atom {
+            Atom::Literal(lit) => quote! { ::mini_parc::parsers::ParseMatch(#lit) },
+            Atom::Group(choice) => self.choice(choice)?,
+            Atom::Rust(expr) => quote! { (#expr) },
+            // Rules refer to each other weakly, so that recursive rules are not a reference
+            // cycle, they are kept alive by the cache in `expand`
+            Atom::Rule(name) if self.rules.contains(&name.to_string()) => {
+                quote! { #name.downgrade() }
+            }
+            Atom::Rule(name) => builtin(name)?,
+        })
+    }
+
+    fn expand(&self, first: &Ident) -> syn::Result<TokenStream2> {
+        let left_recursive = left_recursive_rules(self.grammar);
+        let builder = format_ident!("__mini_parc_grammar_{}", first);
+
+        let mut declarations = vec![];
+        let mut definitions = vec![];
+        let mut types = vec![];
+        for rule in &self.grammar.rules {
+            let name = &rule.name;
+            let ty = match &rule.ty {
+                Some(ty) => quote! { #ty },
+                None => quote! { ::std::string::String },
+            };
+            let rule_name = name.to_string();
+            let constructor = if left_recursive.contains(&name.to_string()) {
+                quote! { left_recursive }
+            } else {
+                quote! { new }
+            };
+            declarations.push(quote! {
+                let #name: ::mini_parc::parsers::rule_p::Rule<'static, #ty> =
+                    ::mini_parc::parsers::rule_p::Rule::#constructor().named(#rule_name);
+            });
+
+            let body = self.choice(&rule.body)?;
+            let body = match &rule.ty {
+                Some(_) => body,
+                // Untyped rules output the text they matched
+                None => quote! { #body.recognize() },
+            };
+            definitions.push(quote! { #name.define(#body); });
+            types.push(quote! { ::mini_parc::parsers::rule_p::Rule<'static, #ty> });
+        }
+
+        let names: Vec<&Ident> = self.grammar.rules.iter().map(|r| &r.name).collect();
+        let functions = self.grammar.rules.iter().enumerate().map(|(i, rule)| {
+            let RuleDef { vis, name, .. } = rule;
+            let ty = &types[i];
+            let index = syn::Index::from(i);
+            let doc = format!("The `{name}` rule, generated by `grammar!`");
+            quote! {
+                #[doc = #doc]
+                #vis fn #name() -> #ty {
+                    #builder().#index
+                }
+            }
+        });
+
+        // The rules are built once per thread, and the functions return handles to them
+        Ok(quote! {
+            #[allow(unused_variables, non_snake_case, clippy::type_complexity)]
+            fn #builder() -> (#(#types,)*) {
+                ::std::thread_local! {
+                    static RULES: (#(#types,)*) = {
+                        use ::mini_parc::traits::Parser as _;
+                        #(#declarations)*
+                        #(#definitions)*
+                        (#(#names,)*)
+                    };
+                }
+                RULES.with(|rules| rules.clone())
+            }
+
+            #(#functions)*
+        })
+    }
+}
+
+fn builtin(name: &Ident) -> syn::Result<TokenStream2> {
+    let parser = match name.to_string().as_str() {
+        "any" => quote! { ::mini_parc::parsers::ParseIf(|_| true) },
+        "digit" => quote! { ::mini_parc::parsers::ParseIf(|c| c.is_numeric()) },
+        "alpha" => quote! { ::mini_parc::parsers::ParseIf(|c| c.is_alphabetic()) },
+        "alnum" => quote! { ::mini_parc::parsers::ParseIf(|c| c.is_alphanumeric()) },
+        "ws" => quote! { ::mini_parc::parsers::ParseWhileOrNothing(|c| c.is_whitespace()) },
+        "eof" => quote! { ::mini_parc::parsers::look_p::eof() },
+        _ => {
+            return Err(syn::Error::new(
+                name.span(),
+                format!(
+                    "undefined rule `{name}`, the built in rules are: {}",
+                    BUILTINS.join(", ")
+                ),
+            ))
+        }
+    };
+    Ok(parser)
+}
+
+pub fn expand(grammar: Grammar) -> syn::Result<TokenStream2> {
+    let Some(first) = grammar.rules.first() else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "a grammar needs at least one rule",
+        ));
+    };
+
+    let mut rules = HashSet::new();
+    for rule in &grammar.rules {
+        if !rules.insert(rule.name.to_string()) {
+            return Err(syn::Error::new(
+                rule.name.span(),
+                format!("rule `{}` is defined more than once", rule.name),
+            ));
+        }
+        if rule.ty.is_none() && rule.body.alternatives.iter().any(|s| s.action.is_some()) {
+            return Err(syn::Error::new(
+                rule.body.alternatives[0].items[0].atom_span(),
+                format!(
+                    "rule `{}` has actions, so it needs a type: `{}: Type = ...`",
+                    rule.name, rule.name
+                ),
+            ));
+        }
+    }
+
+    Codegen {
+        rules,
+        grammar: &grammar,
+    }
+    .expand(&first.name.clone())
+}
@@ -0,0 +1,266 @@
+//! `#[derive(Parser)]` and `grammar!` for `mini_parc`, see the `mini_parc::derive` and
+//! `mini_parc::grammar` modules for how the generated parsers behave.
+
+mod grammar;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Fields, GenericArgument, LitStr, Path,
+    PathArguments, Type,
+};
+
+/// Generate a `<Name>Parser` type that implements `mini_parc::traits::Parser`, with
+/// `Output = <Name>`, and implement `mini_parc::derive::Parsable` for the type.
+///
+/// Supported attributes (`#[parse(...)]`):
+/// - `literal = "..."`: on a struct, variant or field, match the literal first.
+/// - `sep = "..."`: on a `Vec<T>` field, parse one or more `T`s separated by the literal.
+/// - `with = "path"`: on a field, parse it with the parser returned by `path()`.
+#[proc_macro_derive(Parser, attributes(parse))]
+pub fn derive_parser(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Define PEG rules inline, each rule becomes a function returning a
+/// `mini_parc::parsers::rule_p::Rule`. See the `mini_parc::grammar` module for the syntax.
+#[proc_macro]
+pub fn grammar(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as grammar::Grammar);
+    grammar::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct ParseAttrs {
+    literal: Option<LitStr>,
+    sep: Option<LitStr>,
+    with: Option<Path>,
+}
+
+fn parse_attrs(attrs: &[Attribute]) -> syn::Result<ParseAttrs> {
+    let mut out = ParseAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("parse")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("literal") {
+                out.literal = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("sep") {
+                out.sep = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("with") {
+                let path: LitStr = meta.value()?.parse()?;
+                out.with = Some(path.parse()?);
+            } else {
+                return Err(meta.error("expected one of: literal, sep, with"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(out)
+}
+
+/// The `T` in `Vec<T>`
+fn vec_item(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let last = path.path.segments.last()?;
+    if last.ident != "Vec" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &last.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(item) => Some(item),
+        _ => None,
+    }
+}
+
+fn describe_literal(lit: &LitStr) -> TokenStream2 {
+    quote! { ::mini_parc::describe::Description::Literal(#lit.to_string()) }
+}
+
+/// Code that parses `input` into `ctor { fields }`, with the user state in `state`,
+/// evaluating to a `ParserRes`, and code that describes it
+fn parse_fields(
+    ctor: TokenStream2,
+    fields: &Fields,
+    prefix: Option<LitStr>,
+) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let mut steps = vec![];
+    let mut parts = vec![];
+    if let Some(lit) = prefix {
+        parts.push(describe_literal(&lit));
+        steps.push(quote! { let rest = ::mini_parc::derive::literal(#lit, &rest)?; });
+    }
+
+    let mut vars = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let attrs = parse_attrs(&field.attrs)?;
+        let var = format_ident!("__field_{}", i);
+        let ty = &field.ty;
+
+        if let Some(lit) = attrs.literal {
+            parts.push(describe_literal(&lit));
+            steps.push(quote! { let rest = ::mini_parc::derive::literal(#lit, &rest)?; });
+        }
+
+        let step = match (attrs.sep, attrs.with) {
+            (Some(sep), with) => {
+                let item = vec_item(ty).ok_or_else(|| {
+                    syn::Error::new_spanned(ty, "`sep` can only be used on Vec fields")
+                })?;
+                let item_parser = match with {
+                    Some(with) => quote! { #with() },
+                    None => quote! { <#item as ::mini_parc::derive::Parsable>::parser() },
+                };
+                parts.push(quote! {
+                    ::mini_parc::derive::describe_separated(
+                        ::mini_parc::traits::Parser::describe(&#item_parser),
+                        #sep,
+                    )
+                });
+                quote! { let (#var, rest) = ::mini_parc::derive::separated(&#item_parser, #sep, &rest, state)?; }
+            }
+            (None, Some(with)) => {
+                parts.push(quote! { ::mini_parc::traits::Parser::describe(&#with()) });
+                quote! { let (#var, rest) = ::mini_parc::derive::field(&#with(), &rest, state)?; }
+            }
+            (None, None) => {
+                let parser = quote! { <#ty as ::mini_parc::derive::Parsable>::parser() };
+                parts.push(quote! { ::mini_parc::traits::Parser::describe(&#parser) });
+                quote! { let (#var, rest) = ::mini_parc::derive::field(&#parser, &rest, state)?; }
+            }
+        };
+        steps.push(step);
+        vars.push(var);
+    }
+
+    let value = match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|f| &f.ident);
+            quote! { #ctor { #(#names: #vars),* } }
+        }
+        Fields::Unnamed(_) => quote! { #ctor(#(#vars),*) },
+        Fields::Unit => quote! { #ctor },
+    };
+
+    let parse = quote! {
+        let rest = input.to_string();
+        #(#steps)*
+        Ok((#value, rest))
+    };
+    let describe = quote! { ::mini_parc::derive::describe_sequence(vec![#(#parts),*]) };
+    Ok((parse, describe))
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "derive(Parser) does not support generic types",
+        ));
+    }
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let parser_name = format_ident!("{}Parser", name);
+    let attrs = parse_attrs(&input.attrs)?;
+    if attrs.sep.is_some() || attrs.with.is_some() {
+        return Err(syn::Error::new_spanned(
+            name,
+            "only `literal` can be used on the type itself",
+        ));
+    }
+
+    let (body, describe) = match &input.data {
+        Data::Struct(data) => parse_fields(quote! { #name }, &data.fields, attrs.literal)?,
+        Data::Enum(data) => {
+            let mut alternatives = vec![];
+            for variant in &data.variants {
+                let variant_attrs = parse_attrs(&variant.attrs)?;
+                let ident = &variant.ident;
+                // Unit variants are matched by their name, unless a literal is given
+                let literal = match (variant_attrs.literal, &variant.fields) {
+                    (Some(lit), _) => Some(lit),
+                    (None, Fields::Unit) => Some(LitStr::new(&ident.to_string(), ident.span())),
+                    (None, _) => None,
+                };
+                let (body, describe) =
+                    parse_fields(quote! { #name::#ident }, &variant.fields, literal)?;
+                alternatives.push(quote! {
+                    ::mini_parc::derive::FnParser {
+                        function: |input: &str, state: &mut dyn ::mini_parc::state::ParseState|
+                            -> ::mini_parc::type_alias::ParserRes<#name> { #body },
+                        description: || #describe,
+                    }
+                });
+            }
+
+            let Some((first, others)) = alternatives.split_first() else {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "derive(Parser) needs at least one variant",
+                ));
+            };
+            // Variants are tried in order, using `OrThenParser`
+            let chain = others.iter().fold(first.clone(), |acc, alt| {
+                quote! { ::mini_parc::traits::Parser::otherwise(#acc, #alt) }
+            });
+            (
+                quote! { ::mini_parc::traits::Parser::parse_stateful(&#chain, input, state) },
+                quote! { ::mini_parc::traits::Parser::describe(&#chain) },
+            )
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "derive(Parser) does not support unions",
+            ))
+        }
+    };
+
+    let doc = format!("Parser for [`{name}`], generated by `#[derive(Parser)]`");
+    let type_name = name.to_string();
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy, Default)]
+        #vis struct #parser_name;
+
+        impl ::mini_parc::traits::Parser for #parser_name {
+            type Output = #name;
+            fn parse(&self, input: &str) -> ::mini_parc::type_alias::ParserRes<Self::Output> {
+                ::mini_parc::traits::Parser::parse_stateful(self, input, &mut ())
+            }
+
+            // Unit structs and variants do not use the state
+            #[allow(unused_variables)]
+            fn parse_stateful(
+                &self,
+                input: &str,
+                state: &mut dyn ::mini_parc::state::ParseState,
+            ) -> ::mini_parc::type_alias::ParserRes<Self::Output> {
+                #body
+            }
+
+            fn describe(&self) -> ::mini_parc::describe::Description {
+                ::std::thread_local! {
+                    static DESCRIBING: ::std::cell::Cell<bool> = ::std::cell::Cell::new(false);
+                }
+                ::mini_parc::derive::describe_named(#type_name, &DESCRIBING, || #describe)
+            }
+        }
+
+        impl ::mini_parc::derive::Parsable for #name {
+            type Parser = #parser_name;
+            fn parser() -> Self::Parser {
+                #parser_name
+            }
+        }
+    })
+}
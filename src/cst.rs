@@ -0,0 +1,507 @@
+//! A lossless concrete syntax tree, that keeps every character of the input, including the
+//! whitespace and comments (trivia) that parsers usually skip.
+//!
+//! The tree is made in two layers, in the style of "green" and "red" trees:
+//! - `GreenNode`s and `GreenToken`s are immutable and only know their kind, text and width,
+//!   so they can be shared and cached.
+//! - `SyntaxNode`s and `SyntaxToken`s are made on demand from the green tree, and also know
+//!   their offset in the input and their parent.
+//!
+//! Trees are built by parsers: `token` turns the text consumed by any parser into a token,
+//! and `node` groups the tokens and nodes made by a parser into a node. Writing a tree
+//! (with `to_string`) gives back, byte for byte, the input that it was parsed from.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::cst::{line_comment, node, token, trivia, whitespace, SyntaxNode};
+//! use mini_parc::parsers::{ParseMatch, ParseWhile};
+//! use mini_parc::traits::Parser;
+//!
+//! let ws = || trivia(whitespace().otherwise(line_comment("#")));
+//! let assign = node(
+//!     "assign",
+//!     token("name", ParseWhile(|c| c.is_alphabetic()))
+//!         .and_then(ws())
+//!         .and_then(token("eq", ParseMatch("=")))
+//!         .and_then(ws())
+//!         .and_then(token("number", ParseWhile(|c| c.is_numeric())))
+//!         .and_then(ws()),
+//! );
+//!
+//! let input = "x  = 42 # the answer\n";
+//! let (green, rest) = assign.parse(input).unwrap();
+//! assert!(rest.is_empty());
+//!
+//! let tree = SyntaxNode::new_root(green);
+//! assert_eq!(tree.to_string(), input);
+//!
+//! let number = tree.tokens().into_iter().find(|t| t.kind() == "number").unwrap();
+//! assert_eq!((number.text(), number.text_range()), ("42", 5..7));
+//! ```
+
+use std::{fmt::Display, ops::Range, rc::Rc};
+
+use crate::{
+    describe::Description,
+    errors::ParsingError,
+    parsers::{repeat_p::RepeatParser, ParseMatch, ParseWhile, ParseWhileOrNothing},
+    state::ParseState,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// A leaf of the tree, holding some of the input text
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenToken {
+    pub kind: String,
+    pub text: String,
+}
+
+/// An inner node of the tree, its text is the text of all of its children
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenNode {
+    kind: String,
+    width: usize,
+    children: Vec<GreenElement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenToken {
+    pub fn new<K, T>(kind: K, text: T) -> Self
+    where
+        K: Into<String>,
+        T: Into<String>,
+    {
+        Self {
+            kind: kind.into(),
+            text: text.into(),
+        }
+    }
+}
+
+impl GreenNode {
+    pub fn new<K>(kind: K, children: Vec<GreenElement>) -> Self
+    where
+        K: Into<String>,
+    {
+        let width = children.iter().map(GreenElement::width).sum();
+        Self {
+            kind: kind.into(),
+            width,
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// The length of the text of this node, in bytes
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> &str {
+        match self {
+            GreenElement::Node(node) => &node.kind,
+            GreenElement::Token(token) => &token.kind,
+        }
+    }
+
+    /// The length of the text of this element, in bytes
+    pub fn width(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.width,
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+}
+
+impl Display for GreenNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.children
+            .iter()
+            .try_for_each(|child| write!(f, "{child}"))
+    }
+}
+
+impl Display for GreenElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GreenElement::Node(node) => write!(f, "{node}"),
+            GreenElement::Token(token) => write!(f, "{}", token.text),
+        }
+    }
+}
+
+/// Outputs of parsers that hold tree elements, so that they can be collected into a node
+pub trait IntoElements {
+    fn into_elements(self, out: &mut Vec<GreenElement>);
+}
+
+impl IntoElements for GreenElement {
+    fn into_elements(self, out: &mut Vec<GreenElement>) {
+        out.push(self);
+    }
+}
+
+/// For look ahead parsers, which do not consume anything
+impl IntoElements for () {
+    fn into_elements(self, _: &mut Vec<GreenElement>) {}
+}
+
+impl<T> IntoElements for Vec<T>
+where
+    T: IntoElements,
+{
+    fn into_elements(self, out: &mut Vec<GreenElement>) {
+        self.into_iter().for_each(|e| e.into_elements(out));
+    }
+}
+
+impl<T> IntoElements for Option<T>
+where
+    T: IntoElements,
+{
+    fn into_elements(self, out: &mut Vec<GreenElement>) {
+        if let Some(e) = self {
+            e.into_elements(out);
+        }
+    }
+}
+
+/// The outputs of `and_then`
+impl<A, B> IntoElements for (A, B)
+where
+    A: IntoElements,
+    B: IntoElements,
+{
+    fn into_elements(self, out: &mut Vec<GreenElement>) {
+        self.0.into_elements(out);
+        self.1.into_elements(out);
+    }
+}
+
+/// Turn the text consumed by a parser into a token, see `token`
+pub struct TokenParser<P> {
+    pub kind: String,
+    pub parser: P,
+}
+
+impl<P> Parser for TokenParser<P>
+where
+    P: Parser,
+{
+    type Output = GreenElement;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (_, rest) = self.parser.parse_stateful(input, state)?;
+        let text = &input[..input.len() - rest.len()];
+        let token = GreenToken::new(self.kind.clone(), text);
+        Ok((GreenElement::Token(Rc::new(token)), rest))
+    }
+
+    fn describe(&self) -> Description {
+        Description::named(self.kind.clone(), self.parser.describe())
+    }
+}
+
+/// Group the elements made by a parser into a node, see `node`
+pub struct NodeParser<P> {
+    pub kind: String,
+    pub parser: P,
+}
+
+impl<P> Parser for NodeParser<P>
+where
+    P: Parser,
+    P::Output: IntoElements,
+{
+    type Output = GreenElement;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (out, rest) = self.parser.parse_stateful(input, state)?;
+        let mut children = vec![];
+        out.into_elements(&mut children);
+
+        let node = GreenNode::new(self.kind.clone(), children);
+        if node.width != input.len() - rest.len() {
+            // Something was consumed without making a token, so the tree would lose it
+            return Err(ParsingError::PatternNotFound(format!(
+                "node `{}` consumed text that is not in any of its tokens",
+                self.kind
+            )));
+        }
+        Ok((GreenElement::Node(Rc::new(node)), rest))
+    }
+
+    fn describe(&self) -> Description {
+        Description::named(self.kind.clone(), self.parser.describe())
+    }
+}
+
+/// Make a token of the given kind, out of whatever text the parser consumes
+pub fn token<K, P>(kind: K, parser: P) -> TokenParser<P>
+where
+    K: Into<String>,
+    P: Parser,
+{
+    TokenParser {
+        kind: kind.into(),
+        parser,
+    }
+}
+
+/// Make a node of the given kind, with the elements of the parser as children.
+///
+/// Every character that the parser consumes must be part of one of the tokens it makes,
+/// otherwise an error is returned, since the tree would not be lossless.
+pub fn node<K, P>(kind: K, parser: P) -> NodeParser<P>
+where
+    K: Into<String>,
+    P: Parser,
+    P::Output: IntoElements,
+{
+    NodeParser {
+        kind: kind.into(),
+        parser,
+    }
+}
+
+/// One or more whitespace characters, as a `whitespace` token
+pub fn whitespace() -> TokenParser<ParseWhile> {
+    token("whitespace", ParseWhile(|c| c.is_whitespace()))
+}
+
+/// A comment, from `start` to the end of the line (not including the new line), as a
+/// `comment` token
+pub fn line_comment<S>(start: S) -> impl Parser<Output = GreenElement>
+where
+    S: Into<String> + Clone,
+{
+    token(
+        "comment",
+        ParseMatch(start).and_then(ParseWhileOrNothing(|c| c != '\n')),
+    )
+}
+
+/// Zero or more trivia tokens, such as `whitespace` or `line_comment`
+pub fn trivia<P>(parser: P) -> impl Parser<Output = Vec<GreenElement>>
+where
+    P: Parser<Output = GreenElement>,
+{
+    RepeatParser::new(parser).or_default()
+}
+
+/// A node, with its position in the input and its parent
+#[derive(Debug, Clone)]
+pub struct SyntaxNode(Rc<SyntaxData>);
+
+#[derive(Debug)]
+struct SyntaxData {
+    green: Rc<GreenNode>,
+    offset: usize,
+    parent: Option<SyntaxNode>,
+}
+
+/// A token, with its position in the input and its parent
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    offset: usize,
+    parent: SyntaxNode,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    /// The root of a tree, the output of a `node` parser. If `green` is a token, it is
+    /// wrapped in a node of the same kind.
+    pub fn new_root(green: GreenElement) -> Self {
+        let green = match green {
+            GreenElement::Node(node) => node,
+            token @ GreenElement::Token(_) => {
+                Rc::new(GreenNode::new(token.kind().to_string(), vec![token]))
+            }
+        };
+        Self(Rc::new(SyntaxData {
+            green,
+            offset: 0,
+            parent: None,
+        }))
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.0.green.kind
+    }
+
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<&SyntaxNode> {
+        self.0.parent.as_ref()
+    }
+
+    /// Where this node is in the input, in bytes
+    pub fn text_range(&self) -> Range<usize> {
+        self.0.offset..self.0.offset + self.0.green.width
+    }
+
+    pub fn children(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut children = vec![];
+        for child in &self.0.green.children {
+            let element = match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(SyntaxData {
+                    green: green.clone(),
+                    offset,
+                    parent: Some(self.clone()),
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    offset,
+                    parent: self.clone(),
+                }),
+            };
+            offset += child.width();
+            children.push(element);
+        }
+        children
+    }
+
+    /// All the tokens within this node, in order
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = vec![];
+        for child in self.children() {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> &str {
+        &self.green.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+
+    /// Where this token is in the input, in bytes
+    pub fn text_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.text.len()
+    }
+}
+
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.green)
+    }
+}
+
+impl Display for SyntaxToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.green.text)
+    }
+}
+
+#[cfg(test)]
+mod cst_test {
+    use super::{line_comment, node, token, trivia, whitespace, SyntaxElement, SyntaxNode};
+    use crate::parsers::{look_p::not, repeat_p::RepeatParser, ParseIf, ParseMatch, ParseWhile};
+    use crate::traits::Parser;
+
+    #[test]
+    fn round_trip() {
+        let ws = || trivia(whitespace().otherwise(line_comment("//")));
+        let item = node(
+            "item",
+            token("word", ParseWhile(|c| c.is_alphanumeric())).and_then(ws()),
+        );
+        let list = node(
+            "list",
+            ws().and_then(token("open", ParseMatch("[")))
+                .and_then(ws())
+                .and_then(RepeatParser::new(item).optional())
+                .and_then(token("close", ParseMatch("]")))
+                .and_then(ws()),
+        );
+
+        let input = "  // start\n[ a\tb // é\n  c ]\n";
+        let (green, rest) = list.parse(input).unwrap();
+        assert!(rest.is_empty());
+
+        let tree = SyntaxNode::new_root(green);
+        assert_eq!(tree.to_string(), input);
+        assert_eq!(tree.text_range(), 0..input.len());
+
+        let items: Vec<SyntaxNode> = tree
+            .children()
+            .into_iter()
+            .filter_map(|c| match c {
+                SyntaxElement::Node(n) => Some(n),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].to_string(), "b // é\n  ");
+        assert_eq!(&input[items[2].text_range()], "c ");
+        assert_eq!(items[2].parent().unwrap().kind(), "list");
+
+        let comments: Vec<_> = tree
+            .tokens()
+            .into_iter()
+            .filter(|t| t.kind() == "comment")
+            .map(|t| t.text().to_string())
+            .collect();
+        assert_eq!(comments, ["// start", "// é"]);
+    }
+
+    #[test]
+    fn nodes_must_be_lossless() {
+        let skipped = node(
+            "skipped",
+            ParseMatch(" ")
+                .value(())
+                .and_then(token("x", ParseMatch("x"))),
+        );
+        assert!(skipped.parse(" x").is_err());
+
+        let look_ahead = node(
+            "ok",
+            not(ParseIf(|c| c.is_numeric())).and_then(token("x", ParseMatch("x"))),
+        );
+        assert!(look_ahead.parse("x").is_ok());
+    }
+}
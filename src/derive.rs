@@ -0,0 +1,421 @@
+//! Support for `#[derive(Parser)]`, which is enabled with the `derive` feature.
+//!
+//! Deriving `Parser` for a type `Name` generates a `NameParser` type that implements
+//! `Parser<Output = Name>`, and implements `Parsable` for `Name`. Fields are parsed in order,
+//! with the parser of their type (see `Parsable`), and any whitespace before each field or
+//! literal is skipped. Enum variants are tried in order, and unit variants are matched by
+//! their name.
+//!
+//! The type must implement `Debug`, since it is the output of a parser.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::derive::{Parsable, Parser};
+//! use mini_parc::traits::Parser as _;
+//!
+//! #[derive(Debug, PartialEq, Parser)]
+//! enum Protocol {
+//!     #[parse(literal = "tcp")]
+//!     Tcp,
+//!     #[parse(literal = "udp")]
+//!     Udp,
+//! }
+//!
+//! #[derive(Debug, PartialEq, Parser)]
+//! struct Listen {
+//!     #[parse(literal = "port")]
+//!     port: u16,
+//!     protocol: Protocol,
+//!     #[parse(literal = "hosts", sep = ",")]
+//!     hosts: Vec<String>,
+//! }
+//!
+//! let input = r#"port 8080 udp hosts "a", "b""#;
+//! assert_eq!(
+//!     Listen::parser().parse_complete(input),
+//!     Ok(Listen {
+//!         port: 8080,
+//!         protocol: Protocol::Udp,
+//!         hosts: vec!["a".to_string(), "b".to_string()],
+//!     })
+//! );
+//! ```
+
+use std::{cell::Cell, fmt::Debug, thread::LocalKey};
+
+pub use mini_parc_derive::Parser;
+
+use crate::{
+    describe::Description,
+    errors::ParsingError,
+    parsers::{
+        and_p::KeepSecondOutputOnly, repeat_p::RepeatParser, string_p::string_parser, ParseIf,
+        ParseMatch, ParseWhile, ParseWhileOrNothing,
+    },
+    state::ParseState,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// A type that has a default parser, used by `#[derive(Parser)]` for fields without a
+/// `with` attribute.
+pub trait Parsable: Sized {
+    type Parser: Parser<Output = Self>;
+    fn parser() -> Self::Parser;
+}
+
+/// A parser made from a function or closure, which is given the input and the user state
+/// (see `Parser::parse_with`), and a function that describes it (see `Parser::describe`)
+pub struct FnParser<F> {
+    pub function: F,
+    pub description: fn() -> Description,
+}
+
+impl<F, T> Parser for FnParser<F>
+where
+    F: Fn(&str, &mut dyn ParseState) -> ParserRes<T>,
+    T: Debug,
+{
+    type Output = T;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        (self.function)(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        (self.function)(input, state)
+    }
+
+    fn describe(&self) -> Description {
+        (self.description)()
+    }
+}
+
+fn skip_whitespace(input: &str) -> &str {
+    input.trim_start()
+}
+
+/// Skip whitespace, then match a literal, returning the rest of the input
+pub fn literal(lit: &str, input: &str) -> Result<String, ParsingError> {
+    ParseMatch(lit)
+        .parse(skip_whitespace(input))
+        .map(|(_, rest)| rest)
+}
+
+/// Skip whitespace, then run the parser
+pub fn field<P>(parser: &P, input: &str, state: &mut dyn ParseState) -> ParserRes<P::Output>
+where
+    P: Parser,
+{
+    parser.parse_stateful(skip_whitespace(input), state)
+}
+
+/// Parse one or more items, separated by the `sep` literal (and optional whitespace)
+pub fn separated<P>(
+    parser: &P,
+    sep: &str,
+    input: &str,
+    state: &mut dyn ParseState,
+) -> ParserRes<Vec<P::Output>>
+where
+    P: Parser,
+{
+    let (first, mut rest) = field(parser, input, state)?;
+    let mut acc = vec![first];
+    while let Ok(after_sep) = literal(sep, &rest) {
+        let snapshot = state.snapshot();
+        let Ok((item, after_item)) = field(parser, &after_sep, state) else {
+            state.restore(snapshot);
+            break;
+        };
+        acc.push(item);
+        rest = after_item;
+    }
+    Ok((acc, rest))
+}
+
+/// Describe the items parsed by `separated`
+pub fn describe_separated(item: Description, sep: &str) -> Description {
+    let more = Description::Literal(sep.to_string()).then(item.clone());
+    item.then(Description::Repeat {
+        inner: Box::new(more),
+        min: 0,
+    })
+}
+
+/// Describe the parts of a struct or variant, which are parsed one after the other
+pub fn describe_sequence(parts: Vec<Description>) -> Description {
+    parts
+        .into_iter()
+        .reduce(Description::then)
+        .unwrap_or(Description::Sequence(vec![]))
+}
+
+/// Describe a derived type, which is named after the type. `describing` is set while the
+/// type is being described, so that recursive types are only described once.
+pub fn describe_named<F>(
+    name: &str,
+    describing: &'static LocalKey<Cell<bool>>,
+    describe: F,
+) -> Description
+where
+    F: FnOnce() -> Description,
+{
+    if describing.with(|d| d.replace(true)) {
+        return Description::Rule(name.to_string());
+    }
+    let body = describe();
+    describing.with(|d| d.set(false));
+    Description::named(name, body)
+}
+
+/// Parsers of the types that implement `Parsable`
+type ParseFn<T> = FnParser<fn(&str, &mut dyn ParseState) -> ParserRes<T>>;
+
+macro_rules! parsable {
+    ($t:ty, $build:expr) => {
+        impl Parsable for $t {
+            type Parser = ParseFn<$t>;
+            fn parser() -> Self::Parser {
+                fn build() -> impl Parser<Output = $t> {
+                    $build
+                }
+                FnParser {
+                    function: |input, state| build().parse_stateful(input, state),
+                    description: || build().describe(),
+                }
+            }
+        }
+    };
+}
+
+macro_rules! parsable_unsigned {
+    ($($t:ty),*) => {$(
+        parsable!(
+            $t,
+            ParseWhile(|c| c.is_ascii_digit()).with_try_mapping(&|s| s.parse().ok())
+        );
+    )*};
+}
+
+macro_rules! parsable_signed {
+    ($($t:ty),*) => {$(
+        parsable!(
+            $t,
+            ParseMatch("-")
+                .or_default()
+                .and_then(ParseWhile(|c| c.is_ascii_digit()))
+                .with_try_mapping(&|(sign, digits)| format!("{sign}{digits}").parse().ok())
+        );
+    )*};
+}
+
+macro_rules! parsable_float {
+    ($($t:ty),*) => {$(
+        parsable!($t, {
+            let fraction = ParseMatch(".")
+                .and_then(ParseWhileOrNothing(|c| c.is_ascii_digit()))
+                .with_mapping(&|(dot, digits)| dot + &digits)
+                .or_default();
+            ParseMatch("-")
+                .or_default()
+                .and_then(ParseWhile(|c| c.is_ascii_digit()))
+                .and_then(fraction)
+                .with_try_mapping(&|((sign, whole), fraction)| {
+                    format!("{sign}{whole}{fraction}").parse().ok()
+                })
+        });
+    )*};
+}
+
+parsable_unsigned!(u8, u16, u32, u64, u128, usize);
+parsable_signed!(i8, i16, i32, i64, i128, isize);
+parsable_float!(f32, f64);
+
+parsable!(
+    bool,
+    ParseMatch("true")
+        .value(true)
+        .otherwise(ParseMatch("false").value(false))
+);
+
+// Strings are parsed as quoted strings, see `string_parser`
+parsable!(String, string_parser());
+
+parsable!(char, ParseIf(|_| true));
+
+/// One or more items, separated by whitespace
+impl<T> Parsable for Vec<T>
+where
+    T: Parsable + Debug,
+{
+    type Parser = ParseFn<Vec<T>>;
+    fn parser() -> Self::Parser {
+        fn build<T: Parsable + Debug>() -> impl Parser<Output = Vec<T>> {
+            let item = ParseWhileOrNothing(|c| c.is_whitespace())
+                .and_then(T::parser())
+                .combine(KeepSecondOutputOnly);
+            RepeatParser::new(item)
+        }
+        FnParser {
+            function: |input, state| build::<T>().parse_stateful(input, state),
+            description: || build::<T>().describe(),
+        }
+    }
+}
+
+impl<T> Parsable for Option<T>
+where
+    T: Parsable + Debug,
+{
+    type Parser = ParseFn<Option<T>>;
+    fn parser() -> Self::Parser {
+        FnParser {
+            function: |input, state| T::parser().optional().parse_stateful(input, state),
+            description: || T::parser().optional().describe(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod derive_test {
+    use mini_parc_derive::Parser;
+
+    use super::Parsable;
+    use crate::parsers::ParseWhile;
+    use crate::traits::Parser as _;
+
+    fn word() -> impl crate::traits::Parser<Output = String> {
+        ParseWhile(|c| c.is_alphanumeric())
+    }
+
+    /// A word, counted in the user state
+    fn counted_word() -> impl crate::traits::Parser<Output = String> {
+        word().with_state_mapping(&|w, count: &mut usize| {
+            *count += 1;
+            w
+        })
+    }
+
+    #[derive(Debug, PartialEq, Parser)]
+    enum Value {
+        #[parse(literal = "on")]
+        On,
+        #[parse(literal = "off")]
+        Off,
+        Number(i32),
+        Name(#[parse(with = "word")] String),
+    }
+
+    #[derive(Debug, PartialEq, Parser)]
+    #[parse(literal = "set")]
+    struct Setting {
+        #[parse(with = "word")]
+        key: String,
+        #[parse(literal = "=", sep = ",")]
+        values: Vec<Value>,
+    }
+
+    #[derive(Debug, PartialEq, Parser)]
+    struct Point(f64, #[parse(literal = ",")] f64);
+
+    #[derive(Debug, PartialEq, Parser)]
+    struct Words {
+        #[parse(literal = "words", sep = ",", with = "counted_word")]
+        words: Vec<String>,
+    }
+
+    #[derive(Debug, PartialEq, Parser)]
+    enum Item {
+        #[parse(literal = "one")]
+        One(#[parse(with = "counted_word")] String),
+        Many(Words),
+    }
+
+    #[test]
+    fn derived_struct() {
+        let answer = Setting::parser().parse("set debug = on, -3 , off,x rest");
+        assert_eq!(
+            answer,
+            Ok((
+                Setting {
+                    key: "debug".to_string(),
+                    values: vec![
+                        Value::On,
+                        Value::Number(-3),
+                        Value::Off,
+                        Value::Name("x".to_string())
+                    ],
+                },
+                " rest".to_string()
+            ))
+        );
+        assert!(Setting::parser().parse("debug = on").is_err());
+    }
+
+    #[test]
+    fn derived_tuple_struct() {
+        assert_eq!(
+            Point::parser().parse_complete("1.5, -2"),
+            Ok(Point(1.5, -2.0))
+        );
+        assert_eq!(
+            <Option<u8>>::parser().parse("x"),
+            Ok((None, "x".to_string()))
+        );
+    }
+
+    #[test]
+    fn state_is_passed_through() {
+        let mut count = 0_usize;
+        let answer = Item::parser().parse_with("words a, b,c", &mut count);
+        assert_eq!(
+            answer,
+            Ok((
+                Item::Many(Words {
+                    words: vec!["a".to_string(), "b".to_string(), "c".to_string()]
+                }),
+                String::new()
+            ))
+        );
+        assert_eq!(count, 3);
+
+        assert!(Item::parser().parse_with("one x", &mut count).is_ok());
+        assert_eq!(count, 4);
+        // The words need a state
+        assert!(Item::parser().parse("one x").is_err());
+    }
+
+    #[derive(Debug, PartialEq, Parser)]
+    #[parse(literal = ")")]
+    struct Close;
+
+    #[derive(Debug, PartialEq, Parser)]
+    enum Tree {
+        #[parse(literal = "leaf")]
+        Leaf,
+        Node(#[parse(literal = "(")] Vec<Tree>, Close),
+    }
+
+    #[test]
+    fn derived_descriptions() {
+        assert_eq!(
+            Setting::parser().describe().to_string(),
+            "Setting = \"set\" <character>+ \"=\" Value (\",\" Value)* ;\n\
+             Value = \"on\" | \"off\" | \"-\"? <character>+ | <character>+ ;"
+        );
+
+        // Recursive types refer to themselves by name
+        assert_eq!(
+            Tree::parser().parse_complete("((leaf) leaf)"),
+            Ok(Tree::Node(
+                vec![Tree::Node(vec![Tree::Leaf], Close), Tree::Leaf],
+                Close
+            ))
+        );
+        assert_eq!(
+            Tree::parser().describe().to_string(),
+            "Tree = \"leaf\" | \"(\" (<character>* Tree)+ Close ;\nClose = \")\" ;"
+        );
+    }
+}
@@ -0,0 +1,739 @@
+//! Descriptions of the structure of parsers, see `Parser::describe`.
+//!
+//! A `Description` can be displayed as EBNF-like text, or exported as a Graphviz graph
+//! (`to_dot`) or as SVG railroad diagrams (`to_railroad_svg`). Named parts of a parser
+//! (labels, memoized and named rules, ...) are drawn as their own rules, and referred to by
+//! name elsewhere, which is also how recursive rules are described.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::parsers::{repeat_p::RepeatParser, ParseIf, ParseMatch};
+//! use mini_parc::traits::Parser;
+//!
+//! let digit = ParseIf(|c| c.is_numeric()).label("digit");
+//! let number = ParseMatch("-").optional().and_then(RepeatParser::new(digit));
+//! let boolean = ParseMatch("true").otherwise(ParseMatch("false"));
+//! let value = number.value(()).otherwise(boolean.value(())).label("value");
+//!
+//! let description = value.describe();
+//! assert_eq!(
+//!     description.to_string(),
+//!     "value = \"-\"? digit+ | \"true\" | \"false\" ;\ndigit = <character> ;"
+//! );
+//! assert!(description.to_dot().starts_with("digraph"));
+//! assert!(description.to_railroad_svg().starts_with("<svg"));
+//! ```
+
+use std::{
+    fmt::{Debug, Display, Write},
+    rc::Rc,
+};
+
+use crate::generate::CANDIDATES;
+
+/// A named predicate on characters, such as the one of a `ParseIf`. Predicates are equal
+/// when they have the same name, and accept the same characters out of those that inputs are
+/// generated from (see the `generate` module).
+#[derive(Clone)]
+pub struct CharPredicate {
+    pub name: String,
+    pub test: Rc<dyn Fn(char) -> bool>,
+}
+
+impl CharPredicate {
+    pub fn new<S, F>(name: S, test: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(char) -> bool + 'static,
+    {
+        Self {
+            name: name.into(),
+            test: Rc::new(test),
+        }
+    }
+
+    /// Make a predicate that accepts the same characters as `test`, out of those that inputs
+    /// are generated from (see the `generate` module). Unlike `new`, the predicate does not
+    /// need to be kept, so it may borrow.
+    pub fn sampled<S, F>(name: S, test: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(char) -> bool,
+    {
+        let accepted: String = CANDIDATES.chars().filter(|&c| test(c)).collect();
+        Self::new(name, move |c| accepted.contains(c))
+    }
+}
+
+impl Debug for CharPredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CharPredicate({:?})", self.name)
+    }
+}
+
+impl PartialEq for CharPredicate {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && (Rc::ptr_eq(&self.test, &other.test)
+                || CANDIDATES
+                    .chars()
+                    .all(|c| (self.test)(c) == (other.test)(c)))
+    }
+}
+
+/// The structure of a parser
+#[derive(Debug, Clone, PartialEq)]
+pub enum Description {
+    /// Parsers run one after the other
+    Sequence(Vec<Description>),
+    /// Parsers tried in order
+    Choice(Vec<Description>),
+    /// A parser run `min` or more times
+    Repeat {
+        inner: Box<Description>,
+        min: usize,
+    },
+    Optional(Box<Description>),
+    /// Some exact text
+    Literal(String),
+    /// A single character that satisfies a predicate
+    Char(CharPredicate),
+    /// Input that matches some other condition, such as a quoted string
+    Predicate(String),
+    /// A parser that does not consume anything, and succeeds if the inner parser does (or,
+    /// when `negated`, if it fails)
+    LookAhead {
+        inner: Box<Description>,
+        negated: bool,
+    },
+    /// The end of the input
+    End,
+    /// A named parser, and its description
+    Named {
+        name: String,
+        inner: Box<Description>,
+    },
+    /// A reference to a named parser, described elsewhere
+    Rule(String),
+}
+
+impl Description {
+    pub fn named<S>(name: S, inner: Description) -> Self
+    where
+        S: Into<String>,
+    {
+        Description::Named {
+            name: name.into(),
+            inner: Box::new(inner),
+        }
+    }
+
+    /// Make a description optional, so that `x+?` becomes `x*`
+    pub fn optional(self) -> Self {
+        match self {
+            Description::Repeat { inner, min: 1 } => Description::Repeat { inner, min: 0 },
+            d @ (Description::Optional(_) | Description::Repeat { min: 0, .. }) => d,
+            d => Description::Optional(Box::new(d)),
+        }
+    }
+
+    /// Join two descriptions into a sequence, flattening nested sequences
+    pub fn then(self, other: Description) -> Self {
+        let mut items = match self {
+            Description::Sequence(items) => items,
+            d => vec![d],
+        };
+        match other {
+            Description::Sequence(more) => items.extend(more),
+            d => items.push(d),
+        }
+        Description::Sequence(items)
+    }
+
+    /// Join two descriptions into a choice, flattening nested choices
+    pub fn or(self, other: Description) -> Self {
+        let mut alts = match self {
+            Description::Choice(alts) => alts,
+            d => vec![d],
+        };
+        match other {
+            Description::Choice(more) => alts.extend(more),
+            d => alts.push(d),
+        }
+        Description::Choice(alts)
+    }
+
+    /// The named parsers within this description (including itself), each with its
+    /// description, in the order they are first found. Inside of the definitions, named
+    /// parsers are replaced with references to them.
+    pub fn definitions(&self) -> Vec<(String, Description)> {
+        let mut defs = vec![];
+        self.collect_definitions(&mut defs);
+        defs
+    }
+
+    fn collect_definitions(&self, defs: &mut Vec<(String, Description)>) {
+        match self {
+            Description::Named { name, inner } => {
+                if !defs.iter().any(|(n, _)| n == name) {
+                    defs.push((name.clone(), inner.with_references()));
+                }
+                inner.collect_definitions(defs);
+            }
+            Description::Sequence(items) | Description::Choice(items) => {
+                items.iter().for_each(|d| d.collect_definitions(defs))
+            }
+            Description::Repeat { inner, .. }
+            | Description::Optional(inner)
+            | Description::LookAhead { inner, .. } => inner.collect_definitions(defs),
+            Description::Literal(_)
+            | Description::Char(_)
+            | Description::Predicate(_)
+            | Description::End
+            | Description::Rule(_) => {}
+        }
+    }
+
+    /// Replace named parsers with references to them
+    fn with_references(&self) -> Description {
+        match self {
+            Description::Named { name, .. } => Description::Rule(name.clone()),
+            Description::Sequence(items) => {
+                Description::Sequence(items.iter().map(|d| d.with_references()).collect())
+            }
+            Description::Choice(alts) => {
+                Description::Choice(alts.iter().map(|d| d.with_references()).collect())
+            }
+            Description::Repeat { inner, min } => Description::Repeat {
+                inner: Box::new(inner.with_references()),
+                min: *min,
+            },
+            Description::Optional(inner) => {
+                Description::Optional(Box::new(inner.with_references()))
+            }
+            Description::LookAhead { inner, negated } => Description::LookAhead {
+                inner: Box::new(inner.with_references()),
+                negated: *negated,
+            },
+            d => d.clone(),
+        }
+    }
+
+    /// The rules to draw: the definitions, and the parser itself if it is not named
+    fn diagrams(&self) -> Vec<(String, Description)> {
+        let mut diagrams = self.definitions();
+        if !matches!(self, Description::Named { .. }) {
+            diagrams.insert(0, ("start".to_string(), self.with_references()));
+        }
+        diagrams
+    }
+
+    /// Write in EBNF-like notation, with `parent` the precedence of the surrounding
+    /// expression (0 for choices, 1 for sequences, 2 for postfix operators)
+    fn write_ebnf(&self, f: &mut std::fmt::Formatter<'_>, parent: u8) -> std::fmt::Result {
+        let (own, text) = match self {
+            Description::Choice(alts) => (0, join(alts, " | ", 1)?),
+            Description::Sequence(items) => (1, join(items, " ", 2)?),
+            Description::Repeat { inner, min } => {
+                let op = match min {
+                    0 => "*".to_string(),
+                    1 => "+".to_string(),
+                    n => format!("{{{n},}}"),
+                };
+                (2, format!("{}{op}", Ebnf(inner, 2)))
+            }
+            Description::Optional(inner) => (2, format!("{}?", Ebnf(inner, 2))),
+            Description::LookAhead { inner, negated } => {
+                let op = if *negated { "!" } else { "&" };
+                (2, format!("{op}{}", Ebnf(inner, 2)))
+            }
+            Description::Literal(lit) => (3, format!("{lit:?}")),
+            Description::Char(p) => (3, format!("<{}>", p.name)),
+            Description::Predicate(p) => (3, format!("<{p}>")),
+            Description::End => (3, "EOF".to_string()),
+            Description::Named { name, .. } | Description::Rule(name) => (3, name.clone()),
+        };
+        if own < parent {
+            write!(f, "({text})")
+        } else {
+            write!(f, "{text}")
+        }
+    }
+
+    /// A Graphviz graph of the parser. Each named parser is a box, with the structure of
+    /// its definition below it, and references to named parsers are edges to their box.
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::default();
+        let diagrams = self.diagrams();
+        for (i, (name, _)) in diagrams.iter().enumerate() {
+            let _ = writeln!(
+                dot.out,
+                "  rule{i} [label={}, shape=box, style=bold];",
+                dot_string(name)
+            );
+        }
+        for (i, (_, description)) in diagrams.iter().enumerate() {
+            let body = dot.node(description, &diagrams);
+            let _ = writeln!(dot.out, "  rule{i} -> {body};");
+        }
+        format!("digraph parser {{\n  rankdir=TB;\n{}}}\n", dot.out)
+    }
+
+    /// SVG railroad diagrams of the parser, one for each named parser (and one for the
+    /// parser itself, if it is not named)
+    pub fn to_railroad_svg(&self) -> String {
+        let diagrams = self.diagrams();
+        let mut body = String::new();
+        let mut y = MARGIN;
+        let mut width: f64 = 0.0;
+        for (name, description) in &diagrams {
+            let layout = Railroad::layout(description);
+            let _ = writeln!(
+                body,
+                r#"<text x="{MARGIN}" y="{}" class="title">{}</text>"#,
+                y + 12.0,
+                xml_escape(name)
+            );
+            y += 24.0;
+
+            // Start and end markers, and the diagram between them
+            let line = y + layout.up;
+            let end = MARGIN + 10.0 + layout.width;
+            let _ = writeln!(
+                body,
+                r#"<path d="M{MARGIN} {} v20 M{MARGIN} {line} h10 M{end} {line} h10 M{} {} v20"/>"#,
+                line - 10.0,
+                end + 10.0,
+                line - 10.0,
+            );
+            Railroad::draw(description, MARGIN + 10.0, line, &mut body);
+
+            width = width.max(end + 10.0 + MARGIN);
+            y += layout.up + layout.down + 2.0 * MARGIN;
+        }
+
+        format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+                "\n<style>path {{ fill: none; stroke: black; stroke-width: 2; }} ",
+                "rect {{ fill: #eef; stroke: black; stroke-width: 2; }} ",
+                "rect.dashed {{ fill: none; stroke-dasharray: 4 4; }} ",
+                "text {{ font: 14px monospace; text-anchor: middle; }} ",
+                "text.title {{ font-weight: bold; text-anchor: start; }}</style>\n",
+                "{body}</svg>\n"
+            ),
+            w = width,
+            h = y,
+            body = body
+        )
+    }
+}
+
+/// Display a description inside of an expression with the given precedence
+struct Ebnf<'d>(&'d Description, u8);
+
+impl<'d> Display for Ebnf<'d> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.write_ebnf(f, self.1)
+    }
+}
+
+fn join(items: &[Description], sep: &str, precedence: u8) -> Result<String, std::fmt::Error> {
+    let mut out = String::new();
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push_str(sep);
+        }
+        write!(out, "{}", Ebnf(item, precedence))?;
+    }
+    Ok(out)
+}
+
+/// Displays every named parser as an EBNF rule, one per line
+impl Display for Description {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, description)) in self.diagrams().iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{name} = {} ;", Ebnf(description, 0))?;
+        }
+        Ok(())
+    }
+}
+
+fn dot_string(s: &str) -> String {
+    format!("{:?}", s)
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[derive(Default)]
+struct Dot {
+    out: String,
+    nodes: usize,
+}
+
+impl Dot {
+    fn add(&mut self, label: &str, shape: &str) -> String {
+        let id = format!("n{}", self.nodes);
+        self.nodes += 1;
+        let _ = writeln!(
+            self.out,
+            "  {id} [label={}, shape={shape}];",
+            dot_string(label)
+        );
+        id
+    }
+
+    /// Add the nodes of a description, returning the id of its top node
+    fn node(&mut self, description: &Description, rules: &[(String, Description)]) -> String {
+        let children: Vec<&Description> = match description {
+            Description::Sequence(items) | Description::Choice(items) => items.iter().collect(),
+            Description::Repeat { inner, .. }
+            | Description::Optional(inner)
+            | Description::LookAhead { inner, .. } => vec![inner],
+            _ => vec![],
+        };
+        let id = match description {
+            Description::Sequence(_) => self.add("sequence", "ellipse"),
+            Description::Choice(_) => self.add("choice", "diamond"),
+            Description::Repeat { min, .. } => self.add(&format!("repeat {min}+"), "ellipse"),
+            Description::Optional(_) => self.add("optional", "ellipse"),
+            Description::LookAhead { negated, .. } => {
+                let label = if *negated {
+                    "not followed by"
+                } else {
+                    "followed by"
+                };
+                self.add(label, "ellipse")
+            }
+            Description::Literal(lit) => self.add(&format!("{lit:?}"), "box, style=rounded"),
+            Description::Char(p) => self.add(&p.name, "box, style=dashed"),
+            Description::Predicate(p) => self.add(p, "box, style=dashed"),
+            Description::End => self.add("end of input", "box, style=rounded"),
+            Description::Named { name, .. } | Description::Rule(name) => {
+                match rules.iter().position(|(n, _)| n == name) {
+                    Some(i) => return format!("rule{i}"),
+                    None => self.add(name, "box"),
+                }
+            }
+        };
+        for child in children {
+            let child = self.node(child, rules);
+            let _ = writeln!(self.out, "  {id} -> {child};");
+        }
+        id
+    }
+}
+
+const MARGIN: f64 = 20.0;
+/// Horizontal space used by the curves of choices and loops
+const ARC: f64 = 20.0;
+const BOX_HEIGHT: f64 = 24.0;
+const CHAR_WIDTH: f64 = 8.5;
+/// Vertical space between the branches of a choice
+const GAP: f64 = 10.0;
+
+/// The size of a railroad diagram, whose line runs `up` below its top
+struct Layout {
+    width: f64,
+    up: f64,
+    down: f64,
+}
+
+/// Layout and drawing of railroad diagrams, in the style of Tab Atkins' railroad-diagrams
+struct Railroad;
+
+impl Railroad {
+    fn text_box(text: &str) -> Layout {
+        Layout {
+            width: text.chars().count() as f64 * CHAR_WIDTH + 20.0,
+            up: BOX_HEIGHT / 2.0,
+            down: BOX_HEIGHT / 2.0,
+        }
+    }
+
+    fn look_ahead_label(negated: bool) -> &'static str {
+        if negated {
+            "not followed by"
+        } else {
+            "followed by"
+        }
+    }
+
+    /// The text of boxes, and whether they are drawn rounded (for terminals)
+    fn box_text(description: &Description) -> Option<(String, bool)> {
+        match description {
+            Description::Literal(lit) => Some((format!("{lit:?}"), true)),
+            Description::Char(p) => Some((p.name.clone(), false)),
+            Description::Predicate(p) => Some((p.clone(), false)),
+            Description::End => Some(("EOF".to_string(), true)),
+            Description::Named { name, .. } | Description::Rule(name) => {
+                Some((name.clone(), false))
+            }
+            _ => None,
+        }
+    }
+
+    fn layout(description: &Description) -> Layout {
+        if let Some((text, _)) = Self::box_text(description) {
+            return Self::text_box(&text);
+        }
+        match description {
+            Description::Sequence(items) => {
+                let layouts: Vec<Layout> = items.iter().map(Self::layout).collect();
+                Layout {
+                    width: (layouts.iter().map(|l| l.width + GAP).sum::<f64>() - GAP).max(0.0),
+                    up: layouts.iter().map(|l| l.up).fold(0.0, f64::max),
+                    down: layouts.iter().map(|l| l.down).fold(0.0, f64::max),
+                }
+            }
+            Description::Choice(alts) => {
+                let layouts: Vec<Layout> = alts.iter().map(Self::layout).collect();
+                let Some(first) = layouts.first() else {
+                    return Layout {
+                        width: 0.0,
+                        up: 0.0,
+                        down: 0.0,
+                    };
+                };
+                let below: f64 = layouts[1..].iter().map(|l| GAP + l.up + l.down).sum();
+                Layout {
+                    width: layouts.iter().map(|l| l.width).fold(0.0, f64::max) + 2.0 * ARC,
+                    up: first.up,
+                    down: first.down + below,
+                }
+            }
+            Description::Optional(inner) => {
+                let inner = Self::layout(inner);
+                Layout {
+                    width: inner.width + 2.0 * ARC,
+                    up: ARC.max(inner.up) + GAP,
+                    down: inner.down,
+                }
+            }
+            Description::Repeat { inner, min } => {
+                let inner = Self::layout(inner);
+                let up = if *min == 0 {
+                    ARC.max(inner.up) + GAP
+                } else {
+                    inner.up
+                };
+                Layout {
+                    width: inner.width + 2.0 * ARC,
+                    up,
+                    down: inner.down + GAP + ARC / 2.0,
+                }
+            }
+            Description::LookAhead { inner, negated } => {
+                let label = Self::text_box(Self::look_ahead_label(*negated));
+                let inner = Self::layout(inner);
+                Layout {
+                    width: (inner.width + 2.0 * GAP).max(label.width),
+                    up: inner.up + 2.0 * GAP + 6.0,
+                    down: inner.down + GAP,
+                }
+            }
+            _ => unreachable!("boxes are handled above"),
+        }
+    }
+
+    /// Draw a description, with its line starting at `(x, y)`
+    fn draw(description: &Description, x: f64, y: f64, out: &mut String) {
+        if let Some((text, rounded)) = Self::box_text(description) {
+            let layout = Self::text_box(&text);
+            let rx = if rounded { BOX_HEIGHT / 2.0 } else { 0.0 };
+            let _ = writeln!(
+                out,
+                r#"<rect x="{x}" y="{}" width="{}" height="{BOX_HEIGHT}" rx="{rx}"/><text x="{}" y="{}">{}</text>"#,
+                y - layout.up,
+                layout.width,
+                x + layout.width / 2.0,
+                y + 5.0,
+                xml_escape(&text)
+            );
+            return;
+        }
+
+        match description {
+            Description::Sequence(items) => {
+                let mut x = x;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        let _ = writeln!(out, r#"<path d="M{} {y} h{GAP}"/>"#, x - GAP);
+                    }
+                    Self::draw(item, x, y, out);
+                    x += Self::layout(item).width + GAP;
+                }
+            }
+            Description::Choice(alts) => {
+                let width = Self::layout(description).width;
+                let mut branch_y = y;
+                for (i, alt) in alts.iter().enumerate() {
+                    let layout = Self::layout(alt);
+                    if i > 0 {
+                        branch_y += GAP + layout.up;
+                    }
+                    // Into the branch, along it, and back out to the main line
+                    let end = x + ARC + layout.width;
+                    let _ = writeln!(
+                        out,
+                        r#"<path d="M{x} {y} C{} {y} {} {branch_y} {} {branch_y} M{end} {branch_y} H{} C{} {branch_y} {} {y} {} {y}"/>"#,
+                        x + ARC / 2.0,
+                        x + ARC / 2.0,
+                        x + ARC,
+                        x + width - ARC,
+                        x + width - ARC / 2.0,
+                        x + width - ARC / 2.0,
+                        x + width,
+                    );
+                    Self::draw(alt, x + ARC, branch_y, out);
+                    branch_y += layout.down;
+                }
+            }
+            Description::Optional(inner) => {
+                Self::draw_skip(x, y, &Self::layout(description), out);
+                Self::draw_through(inner, x, y, out);
+            }
+            Description::Repeat { inner, min } => {
+                let layout = Self::layout(description);
+                let inner_layout = Self::layout(inner);
+                if *min == 0 {
+                    Self::draw_skip(x, y, &layout, out);
+                }
+                Self::draw_through(inner, x, y, out);
+                // The loop back, below the inner parser
+                let loop_y = y + inner_layout.down + GAP;
+                let _ = writeln!(
+                    out,
+                    r#"<path d="M{} {y} C{} {y} {} {loop_y} {} {loop_y} H{} C{} {loop_y} {} {y} {} {y}"/>"#,
+                    x + layout.width - ARC,
+                    x + layout.width - ARC / 2.0,
+                    x + layout.width - ARC / 2.0,
+                    x + layout.width - ARC,
+                    x + ARC,
+                    x + ARC / 2.0,
+                    x + ARC / 2.0,
+                    x + ARC,
+                );
+            }
+            Description::LookAhead { inner, negated } => {
+                let layout = Self::layout(description);
+                let inner_layout = Self::layout(inner);
+                let top = y - inner_layout.up - GAP;
+                let label = Self::look_ahead_label(*negated);
+                // The inner parser is centered, with lines on either side
+                let side = (layout.width - inner_layout.width) / 2.0;
+                let _ = writeln!(
+                    out,
+                    r#"<rect class="dashed" x="{x}" y="{top}" width="{}" height="{}"/><text x="{}" y="{}">{label}</text>"#,
+                    layout.width,
+                    inner_layout.up + inner_layout.down + 2.0 * GAP,
+                    x + layout.width / 2.0,
+                    top - 4.0,
+                );
+                let _ = writeln!(
+                    out,
+                    r#"<path d="M{x} {y} h{side} M{} {y} h{side}"/>"#,
+                    x + side + inner_layout.width,
+                );
+                Self::draw(inner, x + side, y, out);
+            }
+            _ => unreachable!("boxes are handled above"),
+        }
+    }
+
+    /// Draw the inner parser on the main line, between the curves of an optional or loop
+    fn draw_through(inner: &Description, x: f64, y: f64, out: &mut String) {
+        let width = Self::layout(inner).width;
+        let _ = writeln!(
+            out,
+            r#"<path d="M{x} {y} h{ARC} M{} {y} h{ARC}"/>"#,
+            x + ARC + width
+        );
+        Self::draw(inner, x + ARC, y, out);
+    }
+
+    /// Draw the line that skips over an optional part, above the main line
+    fn draw_skip(x: f64, y: f64, layout: &Layout, out: &mut String) {
+        let width = layout.width;
+        let skip_y = y - layout.up + GAP / 2.0;
+        let _ = writeln!(
+            out,
+            r#"<path d="M{x} {y} C{} {y} {} {skip_y} {} {skip_y} H{} C{} {skip_y} {} {y} {} {y}"/>"#,
+            x + ARC / 2.0,
+            x + ARC / 2.0,
+            x + ARC,
+            x + width - ARC,
+            x + width - ARC / 2.0,
+            x + width - ARC / 2.0,
+            x + width,
+        );
+    }
+}
+
+#[cfg(test)]
+mod describe_test {
+    use super::{CharPredicate, Description};
+    use crate::parsers::{
+        look_p::not, repeat_p::RepeatParser, rule_p::Rule, ParseIf, ParseMatch, ParseWhile,
+    };
+    use crate::traits::Parser;
+
+    #[test]
+    fn recursive_rules() {
+        // list = "[" (list | number)* "]"
+        let list: Rule<()> = Rule::new().named("list");
+        let number = ParseWhile(|c| c.is_numeric()).label("number");
+        list.define(
+            ParseMatch("[")
+                .and_then(RepeatParser::new(list.clone().otherwise(number.value(()))).or_default())
+                .and_then(ParseMatch("]"))
+                .value(()),
+        );
+
+        let description = list.describe();
+        assert_eq!(
+            description.to_string(),
+            "list = \"[\" (list | number)* \"]\" ;\nnumber = <character>+ ;"
+        );
+
+        let dot = description.to_dot();
+        assert!(dot.contains("rule0 [label=\"list\", shape=box, style=bold];"));
+        // The recursive reference points back to the rule
+        assert!(dot.contains("-> rule0;"));
+
+        let svg = description.to_railroad_svg();
+        assert_eq!(svg.matches(r#"class="title""#).count(), 2);
+        assert!(svg.contains(">&quot;[&quot;</text>"));
+    }
+
+    #[test]
+    fn structure() {
+        let keyword = ParseMatch("if").and_then(not(ParseIf(|c| c.is_alphanumeric())));
+        let expected = |test: fn(char) -> bool| {
+            Description::Sequence(vec![
+                Description::Literal("if".to_string()),
+                Description::LookAhead {
+                    inner: Box::new(Description::Char(CharPredicate::new("character", test))),
+                    negated: true,
+                },
+            ])
+        };
+        assert_eq!(keyword.describe(), expected(|c| c.is_alphanumeric()));
+        // Predicates with the same name, that accept different characters, are not equal
+        assert_ne!(keyword.describe(), expected(|_| true));
+        assert_ne!(keyword.describe(), expected(|c| c.is_alphabetic()));
+        assert_eq!(
+            keyword.describe().to_string(),
+            r#"start = "if" !<character> ;"#
+        );
+    }
+}
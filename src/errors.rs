@@ -1,6 +1,175 @@
-#[derive(Debug, PartialEq)]
+use std::fmt::Display;
+
+use crate::position::Position;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParsingError {
     PatternNotFound(String),
     CannotParseAnEmptyString,
     MappingError(String),
+    /// A labelled parser failed, the label is a human readable name of what was expected
+    Expected(String),
+    /// Some error, together with the context frames that were pushed while it propagated.
+    ///
+    /// The stack is ordered from the innermost frame to the outermost one.
+    Context {
+        error: Box<ParsingError>,
+        stack: Vec<String>,
+    },
+    /// The parser succeeded, but did not consume all of the input
+    UnconsumedInput {
+        found: char,
+        position: Position,
+    },
+    /// Fewer than `min` of something were found, such as digits for `take_while_m_n`.
+    ///
+    /// `remaining` is the length of the input where they were looked for. Parsers only see
+    /// the rest of the input, so `locate` is used to turn it into a position.
+    TooFew {
+        expected: String,
+        min: usize,
+        found: usize,
+        remaining: usize,
+    },
+    /// The error of a parser made with `Parser::expect` (or any other error that choices and
+    /// repetitions must not backtrack over), and the length of the input that was left where
+    /// it happened. Like `TooFew`, `locate` turns it into a `Located` error.
+    Unlocated {
+        error: Box<ParsingError>,
+        remaining: usize,
+    },
+    /// Some error, and where it happened
+    Located {
+        error: Box<ParsingError>,
+        position: Position,
+    },
 }
+
+impl ParsingError {
+    /// Push a new context frame onto this error
+    ///
+    /// If the error already has a context stack, the frame will be added to the end of it,
+    /// otherwise a new stack will be made.
+    pub fn with_context<S>(self, frame: S) -> Self
+    where
+        S: Into<String>,
+    {
+        match self {
+            ParsingError::Context { error, mut stack } => {
+                stack.push(frame.into());
+                ParsingError::Context { error, stack }
+            }
+            error => ParsingError::Context {
+                error: Box::new(error),
+                stack: vec![frame.into()],
+            },
+        }
+    }
+
+    /// The error without any of its context frames
+    pub fn root(&self) -> &ParsingError {
+        match self {
+            ParsingError::Context { error, .. } => error.root(),
+            error => error,
+        }
+    }
+
+    /// Wrap errors that do not know where they happened (such as `TooFew`) in a `Located`
+    /// error, `input` is the whole input that was parsed
+    pub fn locate(self, input: &str) -> Self {
+        let position = |remaining: usize| {
+            let start = input.len().saturating_sub(remaining);
+            Position::locate(input, input.get(start..).unwrap_or(input))
+        };
+        match self {
+            ParsingError::TooFew { remaining, .. } => ParsingError::Located {
+                position: position(remaining),
+                error: Box::new(self),
+            },
+            ParsingError::Unlocated { error, remaining } => ParsingError::Located {
+                position: position(remaining),
+                error,
+            },
+            ParsingError::Context { error, stack } => ParsingError::Context {
+                error: Box::new(error.locate(input)),
+                stack,
+            },
+            error => error,
+        }
+    }
+
+    /// For errors made by `Parser::expect`, the length of the input that was left where it
+    /// happened
+    pub(crate) fn expected_at(&self) -> Option<usize> {
+        match self.root() {
+            ParsingError::Unlocated { remaining, .. } => Some(*remaining),
+            _ => None,
+        }
+    }
+
+    /// Whether the error is from a `Parser::expect` that failed further into the input than
+    /// the start of `input`. A choice or a repetition that was given `input` fails with such
+    /// an error, rather than backtracking.
+    pub(crate) fn is_committed(&self, input: &str) -> bool {
+        self.expected_at()
+            .is_some_and(|remaining| remaining < input.len())
+    }
+
+    /// The error of a choice whose branches all failed without being committed: the error
+    /// of the last branch that knows where it failed, or `otherwise`
+    pub(crate) fn of_choice<I>(errors: I, otherwise: ParsingError) -> ParsingError
+    where
+        I: IntoIterator<Item = ParsingError>,
+    {
+        errors
+            .into_iter()
+            .filter(|e| e.expected_at().is_some())
+            .last()
+            .unwrap_or(otherwise)
+    }
+
+    /// The context frames of this error, from innermost to outermost
+    pub fn context_stack(&self) -> &[String] {
+        match self {
+            ParsingError::Context { stack, .. } => stack,
+            _ => &[],
+        }
+    }
+}
+
+impl Display for ParsingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParsingError::PatternNotFound(msg) => write!(f, "{msg}"),
+            ParsingError::CannotParseAnEmptyString => write!(f, "cannot parse an empty string"),
+            ParsingError::MappingError(msg) => write!(f, "{msg}"),
+            ParsingError::Expected(label) => write!(f, "expected {label}"),
+            ParsingError::UnconsumedInput { found, position } => {
+                write!(f, "unexpected {found:?} at {position}")
+            }
+            ParsingError::TooFew {
+                expected,
+                min,
+                found,
+                ..
+            } => {
+                let plural = if *min == 1 { "" } else { "s" };
+                write!(
+                    f,
+                    "expected at least {min} {expected}{plural}, found {found}"
+                )
+            }
+            ParsingError::Unlocated { error, .. } => write!(f, "{error}"),
+            ParsingError::Located { error, position } => write!(f, "{error} at {position}"),
+            ParsingError::Context { error, stack } => {
+                write!(f, "{error}")?;
+                for frame in stack {
+                    write!(f, ", {frame}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParsingError {}
@@ -0,0 +1,520 @@
+//! Fuzz targets for the built in parsers, which should never panic, whatever their input.
+//! This module is enabled with the `fuzzing` feature.
+//!
+//! Each target runs a group of parsers on some input bytes (decoded lossily as UTF-8), and
+//! only checks that they return, since errors are expected for most inputs. The targets are
+//! run by the `cargo-fuzz` crate in the `fuzz` directory (`cargo fuzz run primitives`), and
+//! can also be run offline with `run`, which mutates a seed corpus with a seeded random
+//! number generator, so that any crash can be reproduced.
+//!
+//! Inputs are cut to `MAX_LEN` bytes, since deeply nested input makes recursive rules
+//! overflow the stack, which is a limit of the grammar rather than a bug of a parser. The
+//! recursive rules of the targets refer to themselves with `Rule::downgrade`, so that running
+//! a target many times does not leak them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::fuzz::{run, target};
+//!
+//! let primitives = target("primitives").unwrap();
+//! let corpus = vec![b"\"a\\\"b\"".to_vec(), "é€ 12".as_bytes().to_vec()];
+//! assert_eq!(run(primitives, &corpus, 200, 1), Ok(()));
+//! ```
+
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    fs, io,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+};
+
+#[cfg(feature = "derive")]
+use crate::derive::Parsable;
+#[cfg(feature = "regex")]
+use crate::parsers::regex_p::{ParseCaptures, ParseRegex};
+use crate::{
+    cst::{line_comment, node, token, trivia, whitespace, SyntaxElement, SyntaxNode},
+    generate::Rng,
+    json::Dialect,
+    lexer::{self, any_token, Lexer, Token},
+    parsers::{
+        and_p::{KeepFirstOutputOnly, KeepNone, KeepSecondOutputOnly},
+        class_p::{none_of, one_of, CharSet},
+        either_p::alt3,
+        ident_p::identifier,
+        indent_p::{column, indented_block, line_fold, parse_indented, position, same_indent},
+        look_p::{eof, not, peek},
+        memo_p::MemoCache,
+        profile_p::Profiler,
+        repeat_p::{count_many, fold_many, skip_many, RepeatParser},
+        rule_p::Rule,
+        string_p::string_parser,
+        take_p::{take_n, take_until, take_while_m_n},
+        trace_p, ParseIf, ParseMatch, ParseWhile, ParseWhileOrNothing,
+    },
+    runtime_grammar::Grammar,
+    traits::{Parser, StreamParser},
+};
+
+/// The longest input that is given to the parsers of a target
+pub const MAX_LEN: usize = 512;
+
+/// A fuzz target, a function that runs some of the built in parsers on the input
+pub struct Target {
+    pub name: &'static str,
+    pub run: fn(&[u8]),
+}
+
+/// All of the fuzz targets, one per group of parsers
+pub const TARGETS: &[Target] = &[
+    Target {
+        name: "primitives",
+        run: primitives,
+    },
+    Target {
+        name: "combinators",
+        run: combinators,
+    },
+    Target {
+        name: "rules",
+        run: rules,
+    },
+    Target {
+        name: "indent",
+        run: indent,
+    },
+    Target {
+        name: "cst",
+        run: cst,
+    },
+    Target {
+        name: "runtime_grammar",
+        run: runtime_grammar,
+    },
+    Target {
+        name: "lexer",
+        run: lexer,
+    },
+];
+
+pub fn target(name: &str) -> Option<&'static Target> {
+    TARGETS.iter().find(|t| t.name == name)
+}
+
+fn text(data: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(&data[..data.len().min(MAX_LEN)])
+}
+
+/// `ParseMatch`, `ParseIf`, `ParseWhile`, `ParseWhileOrNothing`, `string_parser`, and (with
+/// the `derive` feature) the parsers of the `Parsable` types
+pub fn primitives(data: &[u8]) {
+    let input = &*text(data);
+    let _ = ParseMatch("if").parse(input);
+    let _ = ParseMatch("é€").parse(input);
+    let _ = ParseMatch('"').parse(input);
+    let _ = ParseIf(|c| c.is_alphabetic()).parse(input);
+    let _ = ParseWhile(|c| c.is_numeric()).parse(input);
+    let _ = ParseWhileOrNothing(|c| !c.is_ascii()).parse(input);
+    let _ = one_of("+-é").union('a'..='z').parse(input);
+    let _ = ParseWhile(
+        none_of("\n")
+            .difference(CharSet::from('0'..='9'))
+            .predicate(),
+    )
+    .parse(input);
+    let _ = take_n(3).parse(input);
+    let _ = take_n(3).bytes().parse_complete(input);
+    let _ = take_until("*/").parse(input);
+    let _ = identifier().reserved(["if"]).keyword("if").parse(input);
+    let _ = identifier().reserved(["if"]).parse(input);
+    let _ = Dialect::JSON.parse(input);
+    let _ = Dialect::JSON5.parse_lines(input);
+    let _ = Dialect::JSONC.parser().parse(input);
+    let _ = take_while_m_n(1, 3, |c| c.is_alphabetic())
+        .bytes()
+        .parse_complete(input);
+    let _ = string_parser().parse(input);
+    let _ = string_parser().parse_complete(input);
+
+    #[cfg(feature = "derive")]
+    {
+        let _ = u8::parser().parse(input);
+        let _ = i64::parser().parse(input);
+        let _ = f64::parser().parse(input);
+        let _ = bool::parser().parse(input);
+        let _ = String::parser().parse(input);
+    }
+}
+
+/// Sequences, choices, repetition, mapping, labels and look ahead
+pub fn combinators(data: &[u8]) {
+    let input = &*text(data);
+    let word = || ParseWhile(|c| c.is_alphabetic());
+    let number = || ParseWhile(|c| c.is_ascii_digit()).with_try_mapping(&|s| s.parse::<u32>().ok());
+    let space = || ParseWhileOrNothing(|c| c.is_whitespace());
+
+    let pair = word()
+        .and_then(ParseMatch("="))
+        .combine(KeepFirstOutputOnly)
+        .and_then(number().optional())
+        .label("pair")
+        .context("in a pair");
+    let _ = pair.parse(input);
+    let _ = pair.parse_complete(input);
+
+    let items = RepeatParser::new(
+        space()
+            .and_then(word().or_either(number()))
+            .combine(KeepSecondOutputOnly),
+    );
+    let _ = items.parse(input);
+    let _ = space()
+        .and_then(word())
+        .combine(KeepNone)
+        .collect_into::<Vec<_>>()
+        .parse(input);
+    let _ = fold_many(number().and_then(space()), 0u64, &|acc, (n, _)| {
+        acc + u64::from(n)
+    })
+    .parse(input);
+    let _ = count_many(ParseMatch("a")).parse(input);
+    let _ = skip_many(space().and_then(ParseMatch(","))).parse(input);
+
+    let _ = alt3(word(), number(), string_parser())
+        .recognize()
+        .parse(input);
+    let _ = peek(word()).and_then(not(ParseMatch("if"))).parse(input);
+    let _ = ParseMatch("x").or_default().and_then(eof()).parse(input);
+    let _ = ParseMatch("y")
+        .value(1)
+        .otherwise(word().value(2))
+        .parse(input);
+    let _ = word().with_mapping(&|s| s.len()).or_value(0).parse(input);
+    word()
+        .and_then(space())
+        .iter(input)
+        .take(MAX_LEN)
+        .for_each(drop);
+
+    let declare = ParseMatch("let ")
+        .and_then(word())
+        .combine(KeepSecondOutputOnly)
+        .with_state_mapping(&|name: String, names: &mut Vec<String>| names.push(name));
+    let use_name = word().with_state_try_mapping(&|name: String, names: &mut Vec<String>| {
+        names.contains(&name).then_some(())
+    });
+    let statements = RepeatParser::new(space().and_then(declare.otherwise(use_name)));
+    let _ = statements.parse_with(input, &mut Vec::<String>::new());
+}
+
+/// Recursive and left recursive rules, memoized, profiled and traced
+pub fn rules(data: &[u8]) {
+    let input = &*text(data);
+
+    // nested = "(" nested* ")" | "x"
+    let nested: Rule<usize> = Rule::new();
+    nested.define(
+        ParseMatch("(")
+            .and_then(RepeatParser::new(nested.downgrade()).or_default())
+            .and_then(ParseMatch(")"))
+            .with_mapping(&|((_, inner), _): ((String, Vec<usize>), String)| inner.len() + 1)
+            .otherwise(ParseMatch("x").value(1))
+            .trace("nested"),
+    );
+    trace_p::start(input);
+    let _ = nested.parse(input);
+    let _ = trace_p::finish().to_json();
+
+    // sum = sum "+" number | sum "-" number | number
+    let cache = MemoCache::new();
+    let profiler = Profiler::new();
+    let number = ParseWhile(|c| c.is_ascii_digit())
+        .with_try_mapping(&|s| s.parse::<i64>().ok())
+        .memoize(&cache, "number")
+        .profile(&profiler, "number");
+    let sum: Rule<i64> = Rule::left_recursive();
+    sum.define(
+        sum.downgrade()
+            .and_then(ParseMatch("+").otherwise(ParseMatch("-")))
+            .and_then(number)
+            .with_mapping(&|((a, op), b)| match op.as_str() {
+                "+" => a.wrapping_add(b),
+                _ => a.wrapping_sub(b),
+            })
+            .otherwise(ParseWhile(|c| c.is_ascii_digit()).with_try_mapping(&|s| s.parse().ok())),
+    );
+    let _ = cache.parse(&sum, input);
+    let _ = profiler.parse(&sum, input);
+    let _ = profiler.report().to_string();
+}
+
+/// Indentation sensitive parsers
+pub fn indent(data: &[u8]) {
+    let input = &*text(data);
+    let word = || ParseWhile(|c| c.is_alphanumeric());
+
+    // item = word ":" block | word
+    let item: Rule<usize> = Rule::new();
+    item.define(
+        same_indent(
+            word()
+                .and_then(ParseMatch(":"))
+                .and_then(indented_block(item.downgrade()).or_default()),
+        )
+        .with_mapping(&|(_, children): ((String, String), Vec<usize>)| children.len())
+        .otherwise(same_indent(word()).value(0)),
+    );
+    let _ = parse_indented(&RepeatParser::new(item.clone()), input);
+    let _ = parse_indented(
+        &line_fold(RepeatParser::new(
+            word().and_then(ParseMatch(" ").or_default()),
+        )),
+        input,
+    );
+    let _ = parse_indented(&column().and_then(position()).and_then(word()), input);
+}
+
+/// Lossless syntax trees, with trivia
+pub fn cst(data: &[u8]) {
+    let input = &*text(data);
+    let ws = || trivia(whitespace().otherwise(line_comment("#")));
+    let list = node(
+        "list",
+        token("open", ParseMatch("["))
+            .and_then(ws())
+            .and_then(RepeatParser::new(
+                token("item", ParseWhile(|c| c.is_alphanumeric()))
+                    .and_then(ws())
+                    .and_then(token("comma", ParseMatch(",")).optional())
+                    .and_then(ws()),
+            ))
+            .and_then(token("close", ParseMatch("]"))),
+    );
+    if let Ok((green, _)) = list.parse(input) {
+        let tree = SyntaxNode::new_root(green);
+        let _ = tree.to_string();
+        for child in tree.children() {
+            if let SyntaxElement::Token(token) = child {
+                let _ = (token.text(), token.text_range(), token.parent().kind());
+            }
+        }
+    }
+}
+
+/// Loading grammars at runtime, and parsing with them. The input is a grammar, optionally
+/// followed by a NUL byte and the input to parse with each of its rules.
+pub fn runtime_grammar(data: &[u8]) {
+    let input = &*text(data);
+    let (grammar, source) = input.split_once('\0').unwrap_or((input, input));
+    for grammar in [Grammar::peg(grammar), Grammar::ebnf(grammar)]
+        .into_iter()
+        .flatten()
+    {
+        for (name, _) in grammar.definitions() {
+            let Some(parser) = grammar.parser(name) else {
+                continue;
+            };
+            if let Ok((node, _)) = parser.parse(source) {
+                let _ = node.text(source);
+            }
+        }
+    }
+}
+
+/// The kinds of tokens made by the `lexer` target
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    Let,
+    Ident,
+    Number,
+    Str,
+    Op,
+    Semi,
+}
+
+/// Tokenizing with a `Lexer`, then parsing the tokens. With the `regex` feature, the input is
+/// also split at a NUL byte into a regular expression, and the input to parse with it.
+pub fn lexer(data: &[u8]) {
+    let input = &*text(data);
+    let lexer = Lexer::new()
+        .rule_with_priority(ParseMatch("let"), Kind::Let, 1)
+        .rule(
+            ParseIf(|c| c.is_alphabetic()).and_then(ParseWhileOrNothing(|c| c.is_alphanumeric())),
+            Kind::Ident,
+        )
+        .rule(take_while_m_n(1, 8, |c| c.is_ascii_digit()), Kind::Number)
+        .rule(string_parser(), Kind::Str)
+        .rule(one_of("+-*/="), Kind::Op)
+        .rule(ParseMatch(";"), Kind::Semi)
+        .skip(ParseWhile(|c| c.is_whitespace()))
+        .skip(ParseMatch("//").and_then(ParseWhileOrNothing(|c| c != '\n')))
+        .skip(
+            ParseMatch("/*")
+                .and_then(take_until("*/"))
+                .and_then(ParseMatch("*/")),
+        );
+    let Ok(tokens) = lexer.lex(input) else {
+        return;
+    };
+    for token in &tokens {
+        let _ = (token.to_string(), &input[token.span.clone()]);
+    }
+
+    // statement = "let"? (ident | number | str) (op (ident | number | str))* ";"?
+    let atom = || {
+        lexer::token(Kind::Ident)
+            .otherwise(lexer::token(Kind::Number))
+            .otherwise(lexer::token(Kind::Str))
+    };
+    let statement = lexer::token(Kind::Let)
+        .optional()
+        .and_then(atom())
+        .and_then(RepeatParser::new(lexer::token(Kind::Op).and_then(atom())).optional())
+        .and_then(lexer::token(Kind::Semi).optional())
+        .with_mapping(&|(((_, first), rest), _)| {
+            let rest: Vec<(Token<Kind>, Token<Kind>)> = rest.unwrap_or_default();
+            first.text.len() + rest.len()
+        });
+    let _ = RepeatParser::new(statement).parse_all_tokens(&tokens);
+    let _ = RepeatParser::new(any_token()).parse_tokens(&tokens);
+
+    #[cfg(feature = "regex")]
+    {
+        let ident = ParseRegex::new(r"[\p{XID_Start}_]\p{XID_Continue}*").unwrap();
+        let _ = Lexer::new()
+            .rule(ident, Kind::Ident)
+            .skip(ParseWhile(|c| c.is_whitespace()))
+            .lex(input);
+
+        let (pattern, source) = input.split_once('\0').unwrap_or((input, input));
+        if let Ok(regex) = ParseRegex::new(pattern) {
+            let _ = regex.parse(source);
+        }
+        if let Ok(captures) = ParseCaptures::new(pattern) {
+            if let Ok((captures, _)) = captures.parse(source) {
+                let _ = (captures.get(1), captures.name("x"));
+            }
+        }
+    }
+}
+
+/// An input that made a target panic
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crash {
+    pub target: &'static str,
+    pub input: Vec<u8>,
+    /// The panic message
+    pub message: String,
+}
+
+impl Display for Crash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "fuzz target `{}` panicked on {:?}: {}",
+            self.target,
+            String::from_utf8_lossy(&self.input),
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for Crash {}
+
+/// Read a corpus directory, with one input per file, as used by `cargo fuzz`
+pub fn load_corpus<P>(dir: P) -> io::Result<Vec<Vec<u8>>>
+where
+    P: AsRef<Path>,
+{
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    paths.sort();
+    paths.into_iter().map(fs::read).collect()
+}
+
+/// Pieces of syntax that mutations insert, to reach the interesting parts of the parsers
+const DICTIONARY: &[&str] = &[
+    "\"", "\\", "(", ")", "[", "]", "{", "}", "\n", "  ", "\t", "\r\n", "\0", "é", "€", "😀", "=",
+    ":", ",", "+", "-", ".", "'", "..", "<-", "::=", "|", "/", "*", "?", "!", "&", "#", "//", "(*",
+    "*)", "^", "0", "9", "1.5", "true", "let ", "if", "x", ";", "/*", "*/", "\\p{L}",
+];
+
+/// Make a new input by changing one of the corpus inputs a few times
+fn mutate(rng: &mut Rng, corpus: &[Vec<u8>]) -> Vec<u8> {
+    let mut input = match corpus.is_empty() {
+        true => vec![],
+        false => corpus[rng.below(corpus.len())].clone(),
+    };
+    for _ in 0..1 + rng.below(4) {
+        let at = rng.below(input.len() + 1);
+        match rng.below(5) {
+            0 if !input.is_empty() => {
+                let i = rng.below(input.len());
+                input[i] ^= 1 << rng.below(8);
+            }
+            1 if !input.is_empty() => {
+                let end = (at + 1 + rng.below(8)).min(input.len());
+                input.drain(at.min(end)..end);
+            }
+            2 if !corpus.is_empty() => {
+                // Splice in part of another input
+                let other = &corpus[rng.below(corpus.len())];
+                let start = rng.below(other.len() + 1);
+                let end = start + rng.below(other.len() - start + 1);
+                input.splice(at..at, other[start..end].iter().copied());
+            }
+            3 => input.truncate(at),
+            _ => {
+                let piece = DICTIONARY[rng.below(DICTIONARY.len())];
+                input.splice(at..at, piece.bytes());
+            }
+        }
+    }
+    input.truncate(MAX_LEN);
+    input
+}
+
+fn run_one(target: &Target, input: &[u8]) -> Result<(), Crash> {
+    catch_unwind(AssertUnwindSafe(|| (target.run)(input))).map_err(|payload| {
+        let message = match payload.downcast_ref::<&str>() {
+            Some(s) => s.to_string(),
+            None => payload
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_else(|| "unknown panic".to_string()),
+        };
+        Crash {
+            target: target.name,
+            input: input.to_vec(),
+            message,
+        }
+    })
+}
+
+/// Run a target on every input of the corpus, then on `iterations` mutations of them,
+/// returning the first input that made it panic
+pub fn run(target: &Target, corpus: &[Vec<u8>], iterations: usize, seed: u64) -> Result<(), Crash> {
+    corpus.iter().try_for_each(|input| run_one(target, input))?;
+    let mut rng = Rng::new(seed);
+    (0..iterations).try_for_each(|_| run_one(target, &mutate(&mut rng, corpus)))
+}
+
+#[cfg(test)]
+mod fuzz_test {
+    use super::{load_corpus, run, TARGETS};
+
+    #[test]
+    fn built_in_parsers_do_not_panic() {
+        for target in TARGETS {
+            let dir = format!("{}/fuzz/corpus/{}", env!("CARGO_MANIFEST_DIR"), target.name);
+            let corpus = load_corpus(&dir).unwrap_or_else(|e| panic!("{dir}: {e}"));
+            assert!(!corpus.is_empty(), "{dir} is empty");
+            if let Err(crash) = run(target, &corpus, 500, 42) {
+                panic!("{crash}");
+            }
+        }
+    }
+}
@@ -0,0 +1,452 @@
+//! Property based testing of parsers, with inputs generated from their structure (see
+//! `Parser::describe`).
+//!
+//! A `Generator` makes random strings that a parser should accept: literals are copied,
+//! characters are sampled until one satisfies the predicate of a `ParseIf` (or similar),
+//! choices pick a random alternative, and repetitions are bounded. Once the generator is
+//! deeper than `max_depth` rules, it picks the shortest way out of the recursion.
+//!
+//! Some inputs may still be rejected, for example when a `ParseWhile` consumes characters
+//! that were generated for the parser after it, or with look ahead, which is skipped. The
+//! harness functions (`check_accepts` and `check_round_trip`) report the first failing
+//! input, so that it can be turned into a regression test.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::generate::{check_round_trip, Generator};
+//! use mini_parc::parsers::{ParseMatch, ParseWhile};
+//! use mini_parc::traits::Parser;
+//!
+//! let word = ParseWhile(|c| c.is_ascii_lowercase());
+//! let pair = word.and_then(ParseMatch("=")).and_then(ParseWhile(|c| c.is_ascii_digit()));
+//!
+//! let input = Generator::new(7).generate(&pair.describe()).unwrap();
+//! assert!(pair.parse_complete(&input).is_ok());
+//!
+//! // Printing what was parsed should give something that parses to the same value
+//! let print = |((key, eq), value): &((String, String), String)| format!("{key}{eq}{value}");
+//! assert_eq!(check_round_trip(&pair, print, 100, 7), Ok(()));
+//! ```
+
+use std::{collections::HashMap, fmt::Display, rc::Rc};
+
+use crate::{
+    describe::{CharPredicate, Description},
+    errors::ParsingError,
+    traits::Parser,
+};
+
+/// A small, seeded, random number generator (SplitMix64), so that failures can be
+/// reproduced from their seed
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, `n` must not be 0
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn chance(&mut self, one_in: usize) -> bool {
+        self.below(one_in) == 0
+    }
+}
+
+/// Characters that are tried when sampling a character predicate
+pub(crate) const CANDIDATES: &str = concat!(
+    "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789",
+    " \t\n\r!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~",
+    "éßλЖ中😀\u{0}"
+);
+
+/// How many random candidates are tried before trying every candidate in order
+const RANDOM_SAMPLES: usize = 32;
+
+/// Makes a string of some input, see `Generator::with_sample`
+type Sampler = Rc<dyn Fn(&mut Rng) -> String>;
+
+/// Generates inputs that a parser should accept, from its description
+pub struct Generator {
+    rng: Rng,
+    max_repeat: usize,
+    max_depth: usize,
+    samples: HashMap<String, Sampler>,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        let string: Sampler = Rc::new(|rng: &mut Rng| {
+            let len = rng.below(6);
+            let text: String = (0..len)
+                .map(|_| (b'a' + rng.below(26) as u8) as char)
+                .collect();
+            format!("\"{text}\"")
+        });
+        Self {
+            rng: Rng::new(seed),
+            max_repeat: 3,
+            max_depth: 8,
+            samples: HashMap::from([("string".to_string(), string)]),
+        }
+    }
+
+    /// Repetitions are generated at most `max_repeat` more times than their minimum
+    pub fn max_repeat(mut self, max_repeat: usize) -> Self {
+        self.max_repeat = max_repeat;
+        self
+    }
+
+    /// After this many nested rules, the shortest alternatives are picked
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Generate input for a named parser or predicate (such as `Description::Predicate`)
+    /// with a function, instead of from its description
+    pub fn with_sample<S, F>(mut self, name: S, sample: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&mut Rng) -> String + 'static,
+    {
+        self.samples.insert(name.into(), Rc::new(sample));
+        self
+    }
+
+    /// Generate one input
+    pub fn generate(&mut self, description: &Description) -> Result<String, ParsingError> {
+        let rules: HashMap<String, Description> = description.definitions().into_iter().collect();
+        let heights = heights(&rules);
+        let mut out = String::new();
+        let mut run = Run {
+            generator: self,
+            rules: &rules,
+            heights: &heights,
+        };
+        run.generate(description, 0, &mut out)?;
+        Ok(out)
+    }
+}
+
+/// How many rules deep the shortest input of each rule is, rules that can only recurse
+/// forever are missing
+fn heights(rules: &HashMap<String, Description>) -> HashMap<String, usize> {
+    let mut heights = HashMap::new();
+    loop {
+        let mut changed = false;
+        for (name, description) in rules {
+            if let Some(h) = height(description, &heights) {
+                if heights.get(name).map_or(true, |&old| h + 1 < old) {
+                    heights.insert(name.clone(), h + 1);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return heights;
+        }
+    }
+}
+
+/// The height of the shortest input of a description, or `None` if it is not known
+fn height(description: &Description, heights: &HashMap<String, usize>) -> Option<usize> {
+    match description {
+        Description::Sequence(items) => items
+            .iter()
+            .map(|d| height(d, heights))
+            .try_fold(0, |acc, h| Some(acc.max(h?))),
+        Description::Choice(alts) => alts.iter().filter_map(|d| height(d, heights)).min(),
+        Description::Repeat { min: 0, .. } | Description::Optional(_) => Some(0),
+        Description::Repeat { inner, .. } => height(inner, heights),
+        Description::Named { name, .. } | Description::Rule(name) => heights.get(name).copied(),
+        Description::LookAhead { .. }
+        | Description::Literal(_)
+        | Description::Char(_)
+        | Description::Predicate(_)
+        | Description::End => Some(0),
+    }
+}
+
+struct Run<'g, 'r> {
+    generator: &'g mut Generator,
+    rules: &'r HashMap<String, Description>,
+    heights: &'r HashMap<String, usize>,
+}
+
+impl<'g, 'r> Run<'g, 'r> {
+    fn too_deep(&self, depth: usize) -> bool {
+        depth >= self.generator.max_depth
+    }
+
+    fn generate(
+        &mut self,
+        description: &Description,
+        depth: usize,
+        out: &mut String,
+    ) -> Result<(), ParsingError> {
+        match description {
+            Description::Sequence(items) => items
+                .iter()
+                .try_for_each(|item| self.generate(item, depth, out)),
+            Description::Choice(alts) => {
+                let alt = if self.too_deep(depth) {
+                    // The alternative that gets out of the recursion the soonest
+                    alts.iter()
+                        .min_by_key(|d| height(d, self.heights).unwrap_or(usize::MAX))
+                } else {
+                    alts.get(self.generator.rng.below(alts.len().max(1)))
+                };
+                match alt {
+                    Some(alt) => self.generate(alt, depth, out),
+                    None => Ok(()),
+                }
+            }
+            Description::Repeat { inner, min } => {
+                let extra = match self.too_deep(depth) {
+                    true => 0,
+                    false => self.generator.rng.below(self.generator.max_repeat + 1),
+                };
+                (0..min + extra).try_for_each(|_| self.generate(inner, depth, out))
+            }
+            Description::Optional(inner) => {
+                if !self.too_deep(depth) && self.generator.rng.chance(2) {
+                    self.generate(inner, depth, out)?;
+                }
+                Ok(())
+            }
+            Description::Literal(lit) => {
+                out.push_str(lit);
+                Ok(())
+            }
+            Description::Char(predicate) => {
+                out.push(self.sample_char(predicate)?);
+                Ok(())
+            }
+            // Look ahead does not consume anything, and the end of input is handled by
+            // not generating anything after it
+            Description::LookAhead { .. } | Description::End => Ok(()),
+            Description::Predicate(name) => match self.generator.samples.get(name).cloned() {
+                Some(sample) => {
+                    out.push_str(&sample(&mut self.generator.rng));
+                    Ok(())
+                }
+                None => Err(ParsingError::PatternNotFound(format!(
+                    "cannot generate input for <{name}>, see Generator::with_sample"
+                ))),
+            },
+            Description::Named { name, inner } => {
+                if let Some(sample) = self.generator.samples.get(name).cloned() {
+                    out.push_str(&sample(&mut self.generator.rng));
+                    return Ok(());
+                }
+                self.generate(inner, depth + 1, out)
+            }
+            Description::Rule(name) => {
+                if let Some(sample) = self.generator.samples.get(name).cloned() {
+                    out.push_str(&sample(&mut self.generator.rng));
+                    return Ok(());
+                }
+                let rules = self.rules;
+                match rules.get(name) {
+                    Some(rule) => self.generate(rule, depth + 1, out),
+                    None => Err(ParsingError::PatternNotFound(format!(
+                        "cannot generate input for undefined rule `{name}`"
+                    ))),
+                }
+            }
+        }
+    }
+
+    fn sample_char(&mut self, predicate: &CharPredicate) -> Result<char, ParsingError> {
+        let candidates: Vec<char> = CANDIDATES.chars().collect();
+        let random =
+            (0..RANDOM_SAMPLES).map(|_| candidates[self.generator.rng.below(candidates.len())]);
+        random
+            .chain(candidates.iter().copied())
+            .find(|&c| (predicate.test)(c))
+            .ok_or_else(|| {
+                ParsingError::PatternNotFound(format!(
+                    "no character was found for <{}>",
+                    predicate.name
+                ))
+            })
+    }
+}
+
+/// An input for which a property did not hold
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyFailure {
+    /// The generated input
+    pub input: String,
+    /// What the output of parsing the input was printed as, for round trips
+    pub printed: Option<String>,
+    pub error: ParsingError,
+}
+
+impl Display for PropertyFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "input {:?}", self.input)?;
+        if let Some(printed) = &self.printed {
+            write!(f, " (printed as {printed:?})")?;
+        }
+        write!(f, " failed: {}", self.error)
+    }
+}
+
+impl std::error::Error for PropertyFailure {}
+
+/// Generate `cases` inputs, and check that the parser accepts all of them, consuming all
+/// of the input
+pub fn check_accepts<P>(parser: &P, cases: usize, seed: u64) -> Result<(), PropertyFailure>
+where
+    P: Parser,
+{
+    let description = parser.describe();
+    let mut generator = Generator::new(seed);
+    for _ in 0..cases {
+        let input = generate(&mut generator, &description)?;
+        if let Err(error) = parser.parse_complete(&input) {
+            return Err(PropertyFailure {
+                input,
+                printed: None,
+                error,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Generate `cases` inputs, and check that parsing each one, printing the output and then
+/// parsing what was printed gives the same output
+pub fn check_round_trip<P, F>(
+    parser: &P,
+    print: F,
+    cases: usize,
+    seed: u64,
+) -> Result<(), PropertyFailure>
+where
+    P: Parser,
+    P::Output: PartialEq,
+    F: Fn(&P::Output) -> String,
+{
+    let description = parser.describe();
+    let mut generator = Generator::new(seed);
+    for _ in 0..cases {
+        let input = generate(&mut generator, &description)?;
+        let failure = |printed, error| PropertyFailure {
+            input: input.clone(),
+            printed,
+            error,
+        };
+
+        let output = parser
+            .parse_complete(&input)
+            .map_err(|e| failure(None, e))?;
+        let printed = print(&output);
+        let reparsed = parser
+            .parse_complete(&printed)
+            .map_err(|e| failure(Some(printed.clone()), e))?;
+        if reparsed != output {
+            return Err(failure(
+                Some(printed),
+                ParsingError::PatternNotFound(format!(
+                    "printed input parsed to {reparsed:?}, instead of {output:?}"
+                )),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn generate(
+    generator: &mut Generator,
+    description: &Description,
+) -> Result<String, PropertyFailure> {
+    generator
+        .generate(description)
+        .map_err(|error| PropertyFailure {
+            input: String::new(),
+            printed: None,
+            error,
+        })
+}
+
+#[cfg(test)]
+mod generate_test {
+    use super::{check_accepts, check_round_trip, Generator};
+    use crate::parsers::{
+        repeat_p::RepeatParser, rule_p::Rule, string_p::string_parser, ParseIf, ParseMatch,
+        ParseWhile,
+    };
+    use crate::runtime_grammar::Grammar;
+    use crate::traits::Parser;
+
+    #[test]
+    fn recursion_is_bounded() {
+        // nested = "(" nested* ")"
+        let nested: Rule<usize> = Rule::new().named("nested");
+        nested.define(
+            ParseMatch("(")
+                .and_then(RepeatParser::new(nested.clone()).or_default())
+                .and_then(ParseMatch(")"))
+                .with_mapping(&|((_, inner), _): ((String, Vec<usize>), String)| {
+                    1 + inner.iter().sum::<usize>()
+                }),
+        );
+
+        let mut generator = Generator::new(1).max_depth(4);
+        for _ in 0..50 {
+            let input = generator.generate(&nested.describe()).unwrap();
+            assert!(nested.parse_complete(&input).is_ok(), "{input}");
+        }
+        assert_eq!(check_accepts(&nested, 50, 2), Ok(()));
+    }
+
+    #[test]
+    fn predicates_and_samples() {
+        let entry = ParseIf(|c| c.is_alphabetic())
+            .and_then(ParseMatch(": "))
+            .and_then(string_parser())
+            .and_then(ParseWhile(|c| c == '!').optional());
+        assert_eq!(check_accepts(&entry, 100, 3), Ok(()));
+
+        let grammar = Grammar::peg("id <- [a-z] [a-z0-9_]* ':' ws\nws <- [ \\t]*").unwrap();
+        let id = grammar.parser("id").unwrap();
+        assert_eq!(check_accepts(&id, 100, 4), Ok(()));
+
+        let hex = ParseWhile(|c| c.is_ascii_hexdigit()).label("hex");
+        let input = Generator::new(5)
+            .with_sample("hex", |_| "ff".to_string())
+            .generate(&hex.describe());
+        assert_eq!(input, Ok("ff".to_string()));
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        // The greedy `ParseWhile` eats the digit that was generated for `ParseIf`
+        let greedy = ParseWhile(|c| c.is_numeric()).and_then(ParseIf(|c| c.is_numeric()));
+        let failure = check_accepts(&greedy, 100, 6).unwrap_err();
+        assert!(failure.input.chars().all(|c| c.is_numeric()));
+
+        // Printing without the separator does not round trip
+        let pair = ParseWhile(|c| c.is_ascii_digit())
+            .and_then(ParseMatch(","))
+            .and_then(ParseWhile(|c| c.is_ascii_digit()));
+        let print = |((a, _), b): &((String, String), String)| format!("{a}{b}");
+        let failure = check_round_trip(&pair, print, 10, 7).unwrap_err();
+        assert!(failure.printed.is_some());
+    }
+}
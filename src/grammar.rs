@@ -0,0 +1,156 @@
+//! Support for the `grammar!` macro, which is enabled with the `grammar` feature.
+//!
+//! `grammar!` turns PEG rules into functions that return a `Rule`, built from the usual
+//! combinators. The rules are built once per thread, and each call returns a handle to
+//! them. Each rule is written as `name = alternatives ;`, or `name: Type = ... ;` when its
+//! alternatives have actions. Alternatives are separated by `|` and tried in order, and an
+//! alternative is a sequence of items, optionally followed by `=> { action }`.
+//!
+//! An item is one of:
+//! - `"literal"`: match the text exactly.
+//! - `rule`: another rule of the grammar, or a built in rule: `any`, `digit`, `alpha`,
+//!   `alnum` (a single `char`), `ws` (any amount of whitespace) or `eof`.
+//! - `( ... )`: a group of alternatives.
+//! - `{ expr }`: any Rust expression that evaluates to a parser.
+//!
+//! Items can be followed by `*` (zero or more, into a `Vec`), `+` (one or more) or `?`
+//! (into an `Option`), and preceded by `&` (look ahead) or `!` (negative look ahead). To use
+//! the output of an item in an action, name it with `name:item`.
+//!
+//! Rules without a type output the text they matched, as do groups of more than one
+//! alternative without actions. Referring to a rule that does not exist is a compile error.
+//! Left recursive rules, such as `expr = expr "+" term | term`, are detected and parsed with
+//! `Rule::left_recursive`.
+//!
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(feature = "grammar")]
+//! # {
+//! use mini_parc::grammar::grammar;
+//! use mini_parc::traits::Parser;
+//!
+//! grammar! {
+//!     expr: i64 = a:expr "+" b:term => { a + b }
+//!               | a:expr "-" b:term => { a - b }
+//!               | term ;
+//!     term: i64 = a:term "*" b:atom => { a * b } | atom ;
+//!     atom: i64 = n:number => { n.parse().unwrap() }
+//!               | "(" e:expr ")" => { e } ;
+//!     number = digit+ ;
+//! }
+//!
+//! assert_eq!(expr().parse_complete("2*(3+4)-5"), Ok(9));
+//! # }
+//! ```
+
+#[cfg(feature = "grammar")]
+pub use mini_parc_derive::grammar;
+
+#[cfg(test)]
+mod grammar_test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use mini_parc_derive::grammar;
+
+    use crate::parsers::{ParseMatch, ParseWhile};
+    use crate::traits::Parser;
+    use crate::type_alias::ParserRes;
+
+    grammar! {
+        pub list: Vec<String> = "[" ws first:item rest:("," ws i:item => { i })* "]" => {
+            let mut items = vec![first];
+            items.extend(rest);
+            items
+        } | "[" ws "]" => { vec![] } ;
+        item = word ws ;
+        word = alpha alnum* | { ParseWhile(|c| c.is_numeric()) } ;
+    }
+
+    grammar! {
+        keyword = ("if" | "else") !alnum ;
+        ident = !keyword alpha+ ;
+        tail: Option<String> = &"x" rest:ident? eof => { rest } ;
+    }
+
+    grammar! {
+        // `b` can match nothing, so `a` is left recursive
+        a = b a "x" | "y" ;
+        b = "z"? ;
+    }
+
+    static BUILT: AtomicUsize = AtomicUsize::new(0);
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    /// Matches "x", and counts how many times it was built and dropped
+    struct Tracked;
+
+    impl Tracked {
+        fn new() -> Self {
+            BUILT.fetch_add(1, Ordering::SeqCst);
+            Tracked
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Parser for Tracked {
+        type Output = String;
+        fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+            ParseMatch("x").parse(input)
+        }
+    }
+
+    grammar! {
+        nested = "(" nested ")" | sum ;
+        sum = sum "+" { Tracked::new() } | { Tracked::new() } ;
+    }
+
+    #[test]
+    fn rules_are_built_once_and_dropped() {
+        std::thread::spawn(|| {
+            assert!(nested().parse_complete("((x+x))").is_ok());
+            assert!(sum().parse_complete("x+x+x").is_ok());
+            assert_eq!(BUILT.load(Ordering::SeqCst), 2);
+            assert_eq!(DROPPED.load(Ordering::SeqCst), 0);
+        })
+        .join()
+        .unwrap();
+        // The rules are freed with the thread, even though they are recursive
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn sequences_and_repetition() {
+        assert_eq!(
+            list().parse_complete("[a1 , b,42]"),
+            Ok(vec!["a1 ".to_string(), "b".to_string(), "42".to_string()])
+        );
+        assert_eq!(list().parse_complete("[ ]"), Ok(vec![]));
+        assert!(list().parse("[a,]").is_err());
+    }
+
+    #[test]
+    fn hidden_left_recursion() {
+        assert_eq!(a().parse("yx"), Ok(("yx".to_string(), String::new())));
+        assert_eq!(a().parse_complete("yxx"), Ok("yxx".to_string()));
+    }
+
+    #[test]
+    fn look_ahead() {
+        assert_eq!(
+            ident().parse("iffy"),
+            Ok(("iffy".to_string(), String::new()))
+        );
+        assert!(ident().parse("if x").is_err());
+        assert_eq!(
+            tail().parse("xy"),
+            Ok((Some("xy".to_string()), String::new()))
+        );
+        assert!(tail().parse("y").is_err());
+    }
+}
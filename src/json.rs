@@ -0,0 +1,748 @@
+//! JSON, and the dialects of it that are used for configuration files and logs.
+//!
+//! A `Dialect` says which extensions to JSON are accepted:
+//! - `Dialect::JSON` is strict JSON (RFC 8259).
+//! - `Dialect::JSONC` adds `//` and `/* */` comments and trailing commas, as in `tsconfig.json`.
+//! - `Dialect::JSON5` also allows unquoted keys, single quoted strings, more escapes, line
+//!   continuations in strings, hexadecimal numbers, `+`, `Infinity` and `NaN`, and numbers
+//!   that start or end with a `.`.
+//!
+//! Every dialect gives the same `Value`s. `Dialect::parse` reads one value, and
+//! `Dialect::parse_lines` reads newline delimited JSON (NDJSON), one value per line. Errors
+//! are `ParsingError::Located`, with the position in the whole input, and have a context
+//! frame for every array element and object value they are inside of.
+//!
+//! The dialects are built from the combinators of this crate, and the fields of a `Dialect`
+//! choose which parsers are combined. Once something has been matched that can only be
+//! followed by one thing, such as a `,` in an array, that thing is required with
+//! `Parser::expect`, so errors point at where the input went wrong. To use a dialect as part
+//! of another parser, see `Dialect::parser`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::json::{Dialect, Value};
+//!
+//! let config = r#"{
+//!     // Emit ES2020
+//!     "target": "es2020",
+//!     "strict": true,
+//! }"#;
+//! let value = Dialect::JSONC.parse(config).unwrap();
+//! assert_eq!(value.get("target"), Some(&Value::String("es2020".to_string())));
+//! assert_eq!(
+//!     Dialect::JSON.parse(config).unwrap_err().to_string(),
+//!     "expected a key at line 2, column 5"
+//! );
+//! assert_eq!(
+//!     Dialect::JSON.parse("{\"a\": [1, x]}").unwrap_err().to_string(),
+//!     "expected a value at line 1, column 11, in array element, \
+//!      in object value for key \"a\""
+//! );
+//!
+//! let value = Dialect::JSON5.parse("{size: 0x10, ratio: .5, name: 'x'}").unwrap();
+//! assert_eq!(value.get("size"), Some(&Value::Number(16.0)));
+//!
+//! let logs = Dialect::JSON.parse_lines("{\"level\": \"info\"}\n{\"level\": \"warn\"}\n").unwrap();
+//! assert_eq!(logs.len(), 2);
+//! ```
+
+use std::{cell::Cell, rc::Rc};
+
+use crate::{
+    describe::Description,
+    errors::ParsingError,
+    parsers::{
+        and_p::{KeepFirstOutputOnly, KeepSecondOutputOnly},
+        either_p::Either,
+        look_p::not,
+        repeat_p::{fold_many, skip_many},
+        rule_p::{BoxedParser, Rule},
+        take_p::{take_until, take_while_m_n},
+        ParseIf, ParseMatch, ParseWhile, ParseWhileOrNothing,
+    },
+    position::Position,
+    state::ParseState,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// Arrays and objects can not be nested deeper than this. Every level is parsed by a few dozen
+/// nested calls, so this keeps to the 2 MiB stack of a thread that was spawned without one
+/// being set, even in debug builds.
+const MAX_DEPTH: usize = 128;
+
+/// A JSON value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    /// Numbers are stored as an `f64`, so those with more significant digits than it has are
+    /// rounded, such as integers above 2^53 (9007199254740992).
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// The members of an object, in the order they were written
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The value of a member of an object. If the key is there more than once, the last one
+    /// is used.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// Which extensions to JSON are accepted, see the `json` module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    /// `//` and `/* */` comments
+    pub comments: bool,
+    /// A comma after the last element of an array or object
+    pub trailing_commas: bool,
+    /// The rest of JSON5: keys, strings, escapes, numbers and whitespace
+    pub json5: bool,
+}
+
+type Boxed<T> = BoxedParser<'static, T>;
+
+fn digits() -> ParseWhile<fn(char) -> bool> {
+    ParseWhile(|c| c.is_ascii_digit())
+}
+
+/// The value of the text of a number, which has already been checked by the grammar
+fn number_value(text: &str) -> Option<f64> {
+    let (sign, body) = match text.strip_prefix('-') {
+        Some(body) => (-1.0, body),
+        None => (1.0, text.strip_prefix('+').unwrap_or(text)),
+    };
+    match body.strip_prefix("0x").or_else(|| body.strip_prefix("0X")) {
+        Some(hex) => Some(
+            sign * hex
+                .chars()
+                .filter_map(|c| c.to_digit(16))
+                .fold(0.0, |n, d| n * 16.0 + d as f64),
+        ),
+        // This also reads `Infinity` and `NaN`
+        None => body.parse::<f64>().ok().map(|n| sign * n),
+    }
+}
+
+fn push<T>(mut items: Vec<T>, item: T) -> Vec<T> {
+    items.push(item);
+    items
+}
+
+fn prepend<T>((first, mut rest): (T, Vec<T>)) -> Vec<T> {
+    rest.insert(0, first);
+    rest
+}
+
+/// Append a run of plain characters, or an escaped character, to a string
+fn push_chunk(mut s: String, chunk: Either<String, Option<char>>) -> String {
+    match chunk {
+        Either::Left(run) => s.push_str(&run),
+        Either::Right(escaped) => s.extend(escaped),
+    }
+    s
+}
+
+impl Dialect {
+    pub const JSON: Dialect = Dialect {
+        comments: false,
+        trailing_commas: false,
+        json5: false,
+    };
+    pub const JSONC: Dialect = Dialect {
+        comments: true,
+        trailing_commas: true,
+        json5: false,
+    };
+    pub const JSON5: Dialect = Dialect {
+        comments: true,
+        trailing_commas: true,
+        json5: true,
+    };
+
+    /// Parse one value, which may be surrounded by whitespace (and comments)
+    pub fn parse(&self, input: &str) -> Result<Value, ParsingError> {
+        self.document().parse_complete(input)
+    }
+
+    /// Parse newline delimited JSON, where every line that is not blank holds one value
+    pub fn parse_lines(&self, input: &str) -> Result<Vec<Value>, ParsingError> {
+        let document = self.document();
+        let mut values = vec![];
+        let mut start = 0;
+        for line in input.split_inclusive('\n') {
+            let end = start + line.len();
+            let text = line.trim_end_matches('\n').trim_end_matches('\r');
+            if !text.trim().is_empty() {
+                // Errors are located by how much of the line was left, so the input is cut
+                // at the end of the line
+                let upto = &input[..start + text.len()];
+                let (value, rest) = document.parse(text).map_err(|e| e.locate(upto))?;
+                if let Some(found) = rest.chars().next() {
+                    return Err(ParsingError::UnconsumedInput {
+                        found,
+                        position: Position::locate(upto, &rest),
+                    });
+                }
+                values.push(value);
+            }
+            start = end;
+        }
+        Ok(values)
+    }
+
+    /// A parser for one value of this dialect. Unlike `parse`, it does not skip whitespace
+    /// before or after the value, and its errors are only given a position by
+    /// `ParsingError::locate` (which `Parser::parse_complete` calls).
+    pub fn parser(self) -> JsonParser {
+        JsonParser(self.value())
+    }
+
+    /// A value, with whitespace (and comments) around it
+    fn document(self) -> impl Parser<Output = Value> {
+        self.skip()
+            .and_then(self.then_skip(self.value()))
+            .combine(KeepSecondOutputOnly)
+    }
+
+    /// Skip whitespace, and comments if the dialect has them
+    fn skip(self) -> Boxed<()> {
+        let space = match self.json5 {
+            true => ParseWhile(|c| c.is_whitespace() || c == '\u{FEFF}').boxed(),
+            false => ParseWhile(|c| matches!(c, ' ' | '\t' | '\n' | '\r')).boxed(),
+        };
+        if !self.comments {
+            return skip_many(space).boxed();
+        }
+        let line = ParseMatch("//")
+            .and_then(ParseWhileOrNothing(|c| c != '\n'))
+            .recognize();
+        let block = ParseMatch("/*")
+            .and_then(
+                take_until("*/")
+                    .and_then(ParseMatch("*/"))
+                    .expect("the end of the comment"),
+            )
+            .recognize();
+        skip_many(space.otherwise(line).otherwise(block)).boxed()
+    }
+
+    /// Follow a parser with whitespace (and comments)
+    fn then_skip<P>(self, parser: P) -> impl Parser<Output = P::Output>
+    where
+        P: Parser,
+    {
+        parser.and_then(self.skip()).combine(KeepFirstOutputOnly)
+    }
+
+    /// The grammar of a value. Arrays and objects refer back to the rule through a weak
+    /// handle, so it is freed when the returned rule is dropped.
+    fn value(self) -> Rule<'static, Value> {
+        let value = Rule::new();
+        let depth = Rc::new(Cell::new(0));
+        let keyword = ParseMatch("null")
+            .value(Value::Null)
+            .otherwise(ParseMatch("true").value(Value::Bool(true)))
+            .otherwise(ParseMatch("false").value(Value::Bool(false)));
+        value.define(
+            keyword
+                .otherwise(self.number())
+                .otherwise(self.string().with_mapping(&Value::String))
+                .otherwise(self.array(&value, &depth))
+                .otherwise(self.object(&value, &depth))
+                .expect("a value"),
+        );
+        value
+    }
+
+    fn array(self, value: &Rule<'static, Value>, depth: &Rc<Cell<usize>>) -> Boxed<Value> {
+        let element = || value.downgrade().context("in array element");
+        ParseMatch("[")
+            .and_then(self.skip())
+            .and_then(Nested {
+                parser: self.items(element, "]", "',' or ']'"),
+                depth: depth.clone(),
+            })
+            .with_mapping(&|(_, elements)| Value::Array(elements))
+            .boxed()
+    }
+
+    fn object(self, value: &Rule<'static, Value>, depth: &Rc<Cell<usize>>) -> Boxed<Value> {
+        let member = || Member {
+            key: self
+                .then_skip(self.key())
+                .and_then(self.then_skip(ParseMatch(":").expect("':'")))
+                .combine(KeepFirstOutputOnly),
+            value: value.downgrade(),
+        };
+        ParseMatch("{")
+            .and_then(self.skip())
+            .and_then(Nested {
+                parser: self.items(member, "}", "',' or '}'"),
+                depth: depth.clone(),
+            })
+            .with_mapping(&|(_, members)| Value::Object(members))
+            .boxed()
+    }
+
+    /// The comma separated items of an array or object, after the opening bracket, up to and
+    /// including the closing one
+    fn items<F, P>(self, item: F, close: &'static str, expected: &str) -> Boxed<Vec<P::Output>>
+    where
+        F: Fn() -> P,
+        P: Parser + 'static,
+        P::Output: Clone,
+    {
+        let mut separator = self.then_skip(ParseMatch(",")).recognize().boxed();
+        if self.trailing_commas {
+            separator = separator
+                .and_then(not(ParseMatch(close)))
+                .recognize()
+                .boxed();
+        }
+        let trailing_comma = match self.trailing_commas {
+            true => self.then_skip(ParseMatch(",")).optional().value(()).boxed(),
+            false => ParseWhileOrNothing(|_| false).value(()).boxed(),
+        };
+        let items = self
+            .then_skip(item())
+            .and_then(fold_many(
+                separator
+                    .and_then(self.then_skip(item()))
+                    .combine(KeepSecondOutputOnly),
+                vec![],
+                &push,
+            ))
+            .with_mapping(&prepend)
+            .and_then(trailing_comma)
+            .combine(KeepFirstOutputOnly)
+            .and_then(ParseMatch(close).expect(expected))
+            .combine(KeepFirstOutputOnly);
+        ParseMatch(close).value(vec![]).otherwise(items).boxed()
+    }
+
+    fn key(self) -> Boxed<String> {
+        if !self.json5 {
+            return self.quoted('"').expect("a key").boxed();
+        }
+        let identifier = ParseIf(|c| c.is_alphabetic() || c == '_' || c == '$')
+            .and_then(ParseWhileOrNothing(|c| {
+                c.is_alphanumeric() || c == '_' || c == '$'
+            }))
+            .recognize();
+        self.string().otherwise(identifier).expect("a key").boxed()
+    }
+
+    fn string(self) -> Boxed<String> {
+        match self.json5 {
+            true => self.quoted('"').otherwise(self.quoted('\'')).boxed(),
+            false => self.quoted('"'),
+        }
+    }
+
+    /// A string between `quote`s
+    fn quoted(self, quote: char) -> Boxed<String> {
+        let plain = ParseWhile(move |c| c != quote && c != '\\' && c >= ' ');
+        let escape = ParseMatch("\\")
+            .and_then(self.escape().expect("an escape"))
+            .combine(KeepSecondOutputOnly);
+        ParseIf(move |c| c == quote)
+            .and_then(fold_many(
+                plain.or_either(escape),
+                String::new(),
+                &push_chunk,
+            ))
+            .combine(KeepSecondOutputOnly)
+            .and_then(ParseIf(move |c| c == quote).expect("the end of the string"))
+            .combine(KeepFirstOutputOnly)
+            .boxed()
+    }
+
+    /// The character of an escape, after the `\`. Line continuations do not make a
+    /// character.
+    fn escape(self) -> Boxed<Option<char>> {
+        let simple = ParseIf(|c| matches!(c, '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't'))
+            .with_mapping(&|c| {
+                Some(match c {
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    c => c,
+                })
+            });
+        let hex4 = || {
+            take_while_m_n(4, 4, |c| c.is_ascii_hexdigit())
+                .with_try_mapping(&|digits| u32::from_str_radix(&digits, 16).ok())
+        };
+        // A surrogate pair, such as `\ud83d\ude00`
+        let low = ParseMatch("\\u")
+            .and_then(hex4())
+            .combine(KeepSecondOutputOnly)
+            .with_try_mapping(&|low| Some(low).filter(|low| (0xDC00..0xE000).contains(low)))
+            .expect("a low surrogate");
+        let pair = hex4()
+            .with_try_mapping(&|high| Some(high).filter(|high| (0xD800..0xDC00).contains(high)))
+            .and_then(low)
+            .with_try_mapping(&|(high, low)| {
+                char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+            });
+        let unicode = ParseMatch("u")
+            .and_then(
+                pair.otherwise(hex4().with_try_mapping(&char::from_u32))
+                    .expect("a unicode escape"),
+            )
+            .with_mapping(&|(_, c)| Some(c));
+        let escape = simple.otherwise(unicode);
+        if !self.json5 {
+            return escape.boxed();
+        }
+
+        let hex2 = take_while_m_n(2, 2, |c| c.is_ascii_hexdigit())
+            .expect("2 hexadecimal digits")
+            .with_try_mapping(&|digits| u32::from_str_radix(&digits, 16).ok());
+        let continuation = ParseMatch("\r\n")
+            .value(None)
+            .otherwise(ParseIf(|c| matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')).value(None));
+        escape
+            .otherwise(ParseMatch("v").value(Some('\u{b}')))
+            // Octal escapes, such as `\01`, are not allowed
+            .otherwise(
+                ParseMatch("0")
+                    .and_then(not(ParseIf(|c| c.is_ascii_digit())))
+                    .value(Some('\0')),
+            )
+            .otherwise(
+                ParseMatch("x")
+                    .and_then(hex2)
+                    .with_mapping(&|(_, code)| char::from_u32(code)),
+            )
+            .otherwise(continuation)
+            .otherwise(ParseIf(|c| !c.is_ascii_digit() && c != 'x' && c != 'u').with_mapping(&Some))
+            .boxed()
+    }
+
+    fn number(self) -> Boxed<Value> {
+        let integer = ParseMatch("0").otherwise(
+            ParseIf(|c| matches!(c, '1'..='9'))
+                .and_then(ParseWhileOrNothing(|c| c.is_ascii_digit()))
+                .recognize(),
+        );
+        let exponent = ParseIf(|c| c == 'e' || c == 'E')
+            .and_then(ParseIf(|c| c == '+' || c == '-').optional())
+            .and_then(digits().expect("a digit"))
+            .recognize();
+        let number = if self.json5 {
+            // `5.` and `.5` are numbers
+            let fraction = ParseMatch(".").and_then(ParseWhileOrNothing(|c| c.is_ascii_digit()));
+            let decimal = integer
+                .and_then(fraction.optional())
+                .recognize()
+                .otherwise(
+                    ParseMatch(".")
+                        .and_then(digits().expect("a digit"))
+                        .recognize(),
+                )
+                .and_then(exponent.optional());
+            let hex = ParseMatch("0x")
+                .otherwise(ParseMatch("0X"))
+                .and_then(ParseWhile(|c| c.is_ascii_hexdigit()).expect("a hexadecimal digit"));
+            let body = ParseMatch("Infinity")
+                .otherwise(ParseMatch("NaN"))
+                .otherwise(hex.recognize())
+                .otherwise(decimal.recognize());
+            ParseIf(|c| c == '+' || c == '-')
+                .optional()
+                .and_then(body.expect("a number"))
+                .recognize()
+                .boxed()
+        } else {
+            let fraction = ParseMatch(".").and_then(digits().expect("a digit"));
+            ParseMatch("-")
+                .optional()
+                .and_then(integer.expect("a digit"))
+                .and_then(fraction.optional())
+                .and_then(exponent.optional())
+                .recognize()
+                .boxed()
+        };
+        number
+            .with_try_mapping(&|text| number_value(&text))
+            .with_mapping(&Value::Number)
+            .boxed()
+    }
+}
+
+/// Fails once arrays and objects are nested more than `MAX_DEPTH` deep, so that deeply
+/// nested input does not overflow the stack
+struct Nested<P> {
+    parser: P,
+    depth: Rc<Cell<usize>>,
+}
+
+impl<P> Parser for Nested<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        if self.depth.get() >= MAX_DEPTH {
+            return Err(ParsingError::Unlocated {
+                error: Box::new(ParsingError::PatternNotFound(format!(
+                    "values are nested more than {MAX_DEPTH} deep"
+                ))),
+                remaining: input.len(),
+            });
+        }
+        self.depth.set(self.depth.get() + 1);
+        let res = self.parser.parse_stateful(input, state);
+        self.depth.set(self.depth.get() - 1);
+        res
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+/// A member of an object, errors in its value get a context frame with its key
+struct Member<K, V> {
+    key: K,
+    value: V,
+}
+
+impl<K, V> Parser for Member<K, V>
+where
+    K: Parser<Output = String>,
+    V: Parser,
+{
+    type Output = (String, V::Output);
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (key, rest) = self.key.parse_stateful(input, state)?;
+        let (value, rest) = self
+            .value
+            .parse_stateful(&rest, state)
+            .map_err(|e| e.with_context(format!("in object value for key {key:?}")))?;
+        Ok(((key, value), rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.key.describe().then(self.value.describe())
+    }
+}
+
+/// Parse one value of a dialect, see `Dialect::parser`
+#[derive(Clone)]
+pub struct JsonParser(Rule<'static, Value>);
+
+impl Parser for JsonParser {
+    type Output = Value;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.0.parse(input)
+    }
+
+    fn describe(&self) -> Description {
+        Description::Predicate("JSON value".to_string())
+    }
+}
+
+#[cfg(test)]
+mod json_test {
+    use super::{Dialect, Value};
+    use crate::errors::ParsingError;
+    use crate::parsers::ParseMatch;
+    use crate::position::Position;
+    use crate::traits::Parser;
+
+    fn error(dialect: Dialect, input: &str) -> String {
+        dialect.parse(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn strict_json() {
+        let value = Dialect::JSON
+            .parse(r#" {"a": [1, -2.5e3, true, null], "b": "\"é😀\n", "a": {}} "#)
+            .unwrap();
+        assert_eq!(
+            value,
+            Value::Object(vec![
+                (
+                    "a".to_string(),
+                    Value::Array(vec![
+                        Value::Number(1.0),
+                        Value::Number(-2500.0),
+                        Value::Bool(true),
+                        Value::Null
+                    ])
+                ),
+                ("b".to_string(), Value::String("\"é😀\n".to_string())),
+                ("a".to_string(), Value::Object(vec![])),
+            ])
+        );
+        assert_eq!(value.get("a"), Some(&Value::Object(vec![])));
+
+        let json = Dialect::JSON;
+        assert_eq!(
+            error(json, "[1,]"),
+            "expected a value at line 1, column 4, in array element"
+        );
+        assert_eq!(error(json, "01"), "unexpected '1' at line 1, column 2");
+        assert_eq!(error(json, "1."), "expected a digit at line 1, column 3");
+        assert_eq!(error(json, "'a'"), "expected a value at line 1, column 1");
+        assert_eq!(
+            error(json, "\"a\nb\""),
+            "expected the end of the string at line 1, column 3"
+        );
+        assert_eq!(
+            error(json, r#""\ud800""#),
+            "expected a low surrogate at line 1, column 8"
+        );
+        assert_eq!(
+            error(json, r#""\q""#),
+            "expected an escape at line 1, column 3"
+        );
+        assert_eq!(error(json, "[1] 2"), "unexpected '2' at line 1, column 5");
+        assert_eq!(error(json, "{\"a\" 1}"), "expected ':' at line 1, column 6");
+        assert_eq!(
+            error(json, "{\"a\": {\"b\": [1, nul]}}"),
+            "expected a value at line 1, column 17, in array element, \
+             in object value for key \"b\", in object value for key \"a\""
+        );
+        assert_eq!(
+            error(json, "[1 2]"),
+            "expected ',' or ']' at line 1, column 4"
+        );
+        assert!(json.parse(&"[".repeat(1000)).is_err());
+        assert!(json.parse(&"{\"a\": ".repeat(1000)).is_err());
+    }
+
+    #[test]
+    fn comments_and_trailing_commas() {
+        let input = "/* settings */ {\n  \"a\": [1, 2,], // two\n}\n";
+        let expected = Value::Object(vec![(
+            "a".to_string(),
+            Value::Array(vec![Value::Number(1.0), Value::Number(2.0)]),
+        )]);
+        assert_eq!(Dialect::JSONC.parse(input), Ok(expected));
+        assert_eq!(
+            error(Dialect::JSONC, "[1] /* x"),
+            "expected the end of the comment at line 1, column 7"
+        );
+        assert_eq!(
+            error(Dialect::JSONC, "{'a': 1}"),
+            "expected a key at line 1, column 2"
+        );
+
+        let dialect = Dialect {
+            trailing_commas: false,
+            ..Dialect::JSONC
+        };
+        assert!(dialect.parse("[1, // one\n 2]").is_ok());
+        assert!(dialect.parse("[1,]").is_err());
+    }
+
+    #[test]
+    fn json5() {
+        let input = r#"{
+            unquoted: 'single "quotes"',
+            $hex: 0xFF, leading: .5, trailing: 5., plus: +1,
+            big: -Infinity,
+            lines: 'a\
+b\x41\v',
+        }"#;
+        let value = Dialect::JSON5.parse(input).unwrap();
+        assert_eq!(
+            value.get("unquoted"),
+            Some(&Value::String("single \"quotes\"".to_string()))
+        );
+        assert_eq!(value.get("$hex"), Some(&Value::Number(255.0)));
+        assert_eq!(value.get("leading"), Some(&Value::Number(0.5)));
+        assert_eq!(value.get("trailing"), Some(&Value::Number(5.0)));
+        assert_eq!(value.get("plus"), Some(&Value::Number(1.0)));
+        assert_eq!(value.get("big"), Some(&Value::Number(f64::NEG_INFINITY)));
+        assert_eq!(
+            value.get("lines"),
+            Some(&Value::String("abA\u{b}".to_string()))
+        );
+        assert!(matches!(Dialect::JSON5.parse("NaN"), Ok(Value::Number(n)) if n.is_nan()));
+        assert_eq!(
+            error(Dialect::JSON5, "0x"),
+            "expected a hexadecimal digit at line 1, column 3"
+        );
+        assert_eq!(
+            Dialect::JSON5.parse(r"'\0'"),
+            Ok(Value::String("\0".to_string()))
+        );
+        assert_eq!(
+            error(Dialect::JSON5, r"'\01'"),
+            "expected an escape at line 1, column 3"
+        );
+    }
+
+    #[test]
+    fn newline_delimited() {
+        let input = "{\"n\": 1}\r\n\n  \n[2]\n{\"n\": }\n";
+        assert_eq!(
+            Dialect::JSON.parse_lines(input),
+            Err(ParsingError::Context {
+                error: Box::new(ParsingError::Located {
+                    error: Box::new(ParsingError::Expected("a value".to_string())),
+                    position: Position {
+                        offset: 24,
+                        line: 5,
+                        column: 7
+                    }
+                }),
+                stack: vec!["in object value for key \"n\"".to_string()]
+            })
+        );
+        let values = Dialect::JSON.parse_lines(&input[..18]).unwrap();
+        assert_eq!(
+            values,
+            [
+                Value::Object(vec![("n".to_string(), Value::Number(1.0))]),
+                Value::Array(vec![Value::Number(2.0)])
+            ]
+        );
+        assert_eq!(Dialect::JSON.parse_lines(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn as_a_parser() {
+        let p = ParseMatch("data=").and_then(Dialect::JSON.parser());
+        assert_eq!(
+            p.parse("data=[true];"),
+            Ok((
+                ("data=".to_string(), Value::Array(vec![Value::Bool(true)])),
+                ";".to_string()
+            ))
+        );
+        // The error is given a position once the whole input is known
+        let error = Dialect::JSON.parser().parse("[x]").unwrap_err();
+        assert_eq!(
+            error.locate("[x]").to_string(),
+            "expected a value at line 1, column 2, in array element"
+        );
+        assert_eq!(
+            p.parse_complete("data=[1, x]").unwrap_err().to_string(),
+            "expected a value at line 1, column 10, in array element"
+        );
+    }
+}
@@ -0,0 +1,356 @@
+//! A tokenizer stage, and primitives to parse the tokens that it makes.
+//!
+//! A `Lexer` is built from rules, each of them is a parser (of strings) and the kind of token
+//! that it makes. At each position, every rule is tried, and the one that consumes the most
+//! input wins. Ties are broken by priority, and then by the order of the rules, so keywords
+//! can be given a higher priority than identifiers. Skip rules (for whitespace and comments)
+//! take part in the same way, but do not make a token.
+//!
+//! The tokens are then parsed with `StreamParser`s, starting from `token(kind)`, which
+//! matches one token of some kind.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::lexer::{token, Lexer, Token};
+//! use mini_parc::parsers::{repeat_p::RepeatParser, ParseMatch, ParseWhile};
+//! use mini_parc::traits::StreamParser;
+//!
+//! #[derive(Debug, Clone, PartialEq)]
+//! enum Kind {
+//!     Number,
+//!     Plus,
+//! }
+//!
+//! let lexer = Lexer::new()
+//!     .rule(ParseWhile(|c| c.is_ascii_digit()), Kind::Number)
+//!     .rule(ParseMatch("+"), Kind::Plus)
+//!     .skip(ParseWhile(|c| c.is_whitespace()));
+//! let tokens = lexer.lex("1 + 22 + 3").unwrap();
+//! assert_eq!(tokens.len(), 5);
+//! assert_eq!((tokens[2].text.as_str(), tokens[2].span.clone()), ("22", 4..6));
+//!
+//! let number = || token(Kind::Number).with_mapping(&|t: Token<Kind>| t.text.parse::<u32>().unwrap());
+//! let sum = number()
+//!     .and_then(RepeatParser::new(token(Kind::Plus).and_then(number())).optional())
+//!     .with_mapping(&|(first, rest)| {
+//!         first + rest.unwrap_or_default().iter().map(|(_, n)| n).sum::<u32>()
+//!     });
+//! assert_eq!(sum.parse_all_tokens(&tokens), Ok(26));
+//! ```
+
+use std::{fmt::Debug, fmt::Display, ops::Range};
+
+use crate::{
+    errors::ParsingError,
+    parsers::rule_p::BoxedParser,
+    position::Position,
+    traits::{Parser, StreamParser},
+    type_alias::TokenRes,
+};
+
+/// A token made by a `Lexer`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token<K> {
+    pub kind: K,
+    /// The text of the input that the token was made from
+    pub text: String,
+    /// Where the token is in the input, in bytes
+    pub span: Range<usize>,
+    /// Where the token starts
+    pub position: Position,
+}
+
+impl<K> Display for Token<K>
+where
+    K: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {:?}", self.kind, self.text)
+    }
+}
+
+struct LexRule<'a, K> {
+    parser: BoxedParser<'a, String>,
+    /// The kind of the tokens made by the rule, `None` for skip rules
+    kind: Option<K>,
+    priority: i32,
+}
+
+/// Turns an input string into a list of tokens, see the `lexer` module
+pub struct Lexer<'a, K> {
+    rules: Vec<LexRule<'a, K>>,
+}
+
+impl<'a, K> Default for Lexer<'a, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, K> Lexer<'a, K> {
+    pub fn new() -> Self {
+        Self { rules: vec![] }
+    }
+
+    /// Make a token of some kind from the input that the parser consumes
+    pub fn rule<P>(self, parser: P, kind: K) -> Self
+    where
+        P: Parser + 'a,
+    {
+        self.rule_with_priority(parser, kind, 0)
+    }
+
+    /// Like `rule`, but when several rules consume the same amount of input, the one with the
+    /// highest priority wins
+    pub fn rule_with_priority<P>(mut self, parser: P, kind: K, priority: i32) -> Self
+    where
+        P: Parser + 'a,
+    {
+        self.rules.push(LexRule {
+            parser: parser.recognize().boxed(),
+            kind: Some(kind),
+            priority,
+        });
+        self
+    }
+
+    /// Skip the input that the parser consumes, such as whitespace or comments
+    pub fn skip<P>(mut self, parser: P) -> Self
+    where
+        P: Parser + 'a,
+    {
+        self.rules.push(LexRule {
+            parser: parser.recognize().boxed(),
+            kind: None,
+            priority: 0,
+        });
+        self
+    }
+}
+
+impl<'a, K> Lexer<'a, K>
+where
+    K: Clone,
+{
+    /// Split all of the input into tokens, failing at the first character that no rule
+    /// matches
+    pub fn lex(&self, input: &str) -> Result<Vec<Token<K>>, ParsingError> {
+        let mut tokens = vec![];
+        let mut position = Position {
+            offset: 0,
+            line: 1,
+            column: 1,
+        };
+
+        while let Some(found) = input[position.offset..].chars().next() {
+            let rest = &input[position.offset..];
+            // The longest match, then the highest priority, then the first rule
+            let best = self
+                .rules
+                .iter()
+                .filter_map(|rule| {
+                    let (_, after) = rule.parser.parse(rest).ok()?;
+                    let len = rest.len().saturating_sub(after.len());
+                    (len > 0).then_some((len, rule))
+                })
+                .reduce(
+                    |best, next| match (next.0, next.1.priority) > (best.0, best.1.priority) {
+                        true => next,
+                        false => best,
+                    },
+                );
+            let Some((len, rule)) = best else {
+                return Err(ParsingError::PatternNotFound(format!(
+                    "no token matches {found:?} at {position}"
+                )));
+            };
+
+            let text = &rest[..len];
+            if let Some(kind) = &rule.kind {
+                tokens.push(Token {
+                    kind: kind.clone(),
+                    text: text.to_string(),
+                    span: position.offset..position.offset + len,
+                    position,
+                });
+            }
+            position = advance(position, text);
+        }
+        Ok(tokens)
+    }
+}
+
+/// The position after some text, which starts at `position`
+fn advance(position: Position, text: &str) -> Position {
+    let column = match text.rfind('\n') {
+        Some(i) => text[i + 1..].chars().count() + 1,
+        None => position.column + text.chars().count(),
+    };
+    Position {
+        offset: position.offset + text.len(),
+        line: position.line + text.matches('\n').count(),
+        column,
+    }
+}
+
+/// Parse one token of some kind, see `token`
+pub struct TokenKind<K>(pub K);
+
+impl<K> StreamParser<K> for TokenKind<K>
+where
+    K: Debug + Clone + PartialEq,
+{
+    type Output = Token<K>;
+    fn parse_tokens<'t>(&self, tokens: &'t [Token<K>]) -> TokenRes<'t, K, Self::Output> {
+        match tokens.split_first() {
+            Some((token, rest)) if token.kind == self.0 => Ok((token.clone(), rest)),
+            Some((token, _)) => Err(ParsingError::PatternNotFound(format!(
+                "expected {:?}, found {token} at {}",
+                self.0, token.position
+            ))),
+            None => Err(ParsingError::PatternNotFound(format!(
+                "expected {:?}, found the end of the input",
+                self.0
+            ))),
+        }
+    }
+}
+
+/// Parse one token of some kind
+pub fn token<K>(kind: K) -> TokenKind<K> {
+    TokenKind(kind)
+}
+
+/// Parse one token of any kind, see `any_token`
+pub struct AnyToken;
+
+impl<K> StreamParser<K> for AnyToken
+where
+    K: Debug + Clone,
+{
+    type Output = Token<K>;
+    fn parse_tokens<'t>(&self, tokens: &'t [Token<K>]) -> TokenRes<'t, K, Self::Output> {
+        match tokens.split_first() {
+            Some((token, rest)) => Ok((token.clone(), rest)),
+            None => Err(ParsingError::CannotParseAnEmptyString),
+        }
+    }
+}
+
+/// Parse one token of any kind
+pub fn any_token() -> AnyToken {
+    AnyToken
+}
+
+#[cfg(test)]
+mod lexer_test {
+    use super::{any_token, token, Lexer, Token};
+    use crate::errors::ParsingError;
+    use crate::parsers::{
+        and_p::{KeepFirstOutputOnly, KeepSecondOutputOnly},
+        repeat_p::RepeatParser,
+        ParseIf, ParseMatch, ParseWhile, ParseWhileOrNothing,
+    };
+    use crate::traits::{Parser, StreamParser};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Kind {
+        Let,
+        Ident,
+        Number,
+        Eq,
+        EqEq,
+        Semi,
+    }
+
+    fn lexer() -> Lexer<'static, Kind> {
+        Lexer::new()
+            .rule(
+                ParseIf(|c| c.is_alphabetic())
+                    .and_then(ParseWhileOrNothing(|c| c.is_alphanumeric())),
+                Kind::Ident,
+            )
+            .rule_with_priority(ParseMatch("let"), Kind::Let, 1)
+            .rule(ParseWhile(|c| c.is_ascii_digit()), Kind::Number)
+            .rule(ParseMatch("="), Kind::Eq)
+            .rule(ParseMatch("=="), Kind::EqEq)
+            .rule(ParseMatch(";"), Kind::Semi)
+            .skip(ParseWhile(|c| c.is_whitespace()))
+            .skip(ParseMatch("//").and_then(ParseWhileOrNothing(|c| c != '\n')))
+    }
+
+    #[test]
+    fn longest_match_and_priority() {
+        let tokens = lexer().lex("let letter = 1; // one\nx == 2;").unwrap();
+        let kinds: Vec<Kind> = tokens.iter().map(|t| t.kind).collect();
+        use Kind::*;
+        assert_eq!(
+            kinds,
+            [Let, Ident, Eq, Number, Semi, Ident, EqEq, Number, Semi]
+        );
+        assert_eq!(tokens[1].text, "letter");
+        assert_eq!((tokens[5].position.line, tokens[5].position.column), (2, 1));
+        assert_eq!(tokens[6].span, 25..27);
+
+        let err = lexer().lex("let é = 1;\nlet $").unwrap_err();
+        assert_eq!(
+            err,
+            ParsingError::PatternNotFound("no token matches '$' at line 2, column 5".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_tokens() {
+        // statement = "let"? ident "=" (number | ident) ";"
+        let value = || token(Kind::Number).otherwise(token(Kind::Ident));
+        let statement = token(Kind::Let)
+            .optional()
+            .and_then(token(Kind::Ident))
+            .combine(KeepSecondOutputOnly)
+            .and_then(token(Kind::Eq))
+            .combine(KeepFirstOutputOnly)
+            .and_then(value())
+            .and_then(token(Kind::Semi))
+            .combine(KeepFirstOutputOnly)
+            .with_mapping(&|(name, value): (Token<Kind>, Token<Kind>)| (name.text, value.text));
+        let program = RepeatParser::new(statement);
+
+        let tokens = lexer().lex("let x = 1;\ny = x;").unwrap();
+        assert_eq!(
+            program.parse_all_tokens(&tokens),
+            Ok(vec![
+                ("x".to_string(), "1".to_string()),
+                ("y".to_string(), "x".to_string())
+            ])
+        );
+
+        let tokens = lexer().lex("let x = ;").unwrap();
+        let err = program.parse_all_tokens(&tokens).unwrap_err();
+        assert_eq!(err.to_string(), "Did not match parser any times");
+        // A choice fails with the error of its last branch
+        assert_eq!(
+            value().parse_tokens(&tokens[3..]),
+            Err(ParsingError::PatternNotFound(
+                "expected Ident, found Semi \";\" at line 1, column 9".to_string()
+            ))
+        );
+        assert_eq!(
+            token(Kind::Let)
+                .and_then(token(Kind::Number))
+                .parse_tokens(&tokens),
+            Err(ParsingError::PatternNotFound(
+                "expected Number, found Ident \"x\" at line 1, column 5".to_string()
+            ))
+        );
+
+        let (_, rest) = RepeatParser::new(any_token())
+            .parse_tokens(&tokens)
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(
+            token(Kind::Semi).parse_all_tokens(&tokens[3..]),
+            Ok(tokens[3].clone())
+        );
+    }
+}
@@ -1,7 +1,24 @@
 #![allow(dead_code)]
 
+// Lets the code generated by `#[derive(Parser)]` and `grammar!` refer to `::mini_parc` inside of this crate
+extern crate self as mini_parc;
+
+pub mod cst;
+#[cfg(feature = "derive")]
+pub mod derive;
+pub mod describe;
 pub mod errors;
+// The fuzz targets are run offline by the tests, even without the `fuzzing` feature
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzz;
+pub mod generate;
+pub mod grammar;
+pub mod json;
+pub mod lexer;
 pub mod parsers;
+pub mod position;
+pub mod runtime_grammar;
+pub mod state;
 pub mod traits;
 pub mod type_alias;
 
@@ -1,33 +1,28 @@
 use std::fmt::Debug;
 
-use crate::{traits::Parser, type_alias::ParserRes};
+use crate::{
+    describe::Description,
+    lexer::Token,
+    state::ParseState,
+    traits::{Parser, StreamParser},
+    type_alias::{ParserRes, TokenRes},
+};
 
 /// A parser that consists of first running some parser A, then some parser B.
 ///
 /// If either of the parsers give an error, that error will be returned. If they
 /// both suceed, their outputs will be combined using the `AndCombinator`, and
 /// that will be reuturned, with the rest of the unparsed string.
-pub struct AndThenParser<A, B, C>
-where
-    A: Parser,
-    B: Parser,
-    C: AndCombinator<A::Output, B::Output>,
-{
+///
+/// It is also a parser of tokens (see `StreamParser`), when A and B are.
+pub struct AndThenParser<A, B, C> {
     pub first_parse: A,
     pub second_parse: B,
     combinator: C,
 }
 
-impl<A, B, C> AndThenParser<A, B, C>
-where
-    A: Parser,
-    B: Parser,
-    C: AndCombinator<A::Output, B::Output>,
-{
-    pub fn combine<NC>(self, combinator: NC) -> AndThenParser<A, B, NC>
-    where
-        NC: AndCombinator<A::Output, B::Output>,
-    {
+impl<A, B, C> AndThenParser<A, B, C> {
+    pub fn combine<NC>(self, combinator: NC) -> AndThenParser<A, B, NC> {
         AndThenParser {
             first_parse: self.first_parse,
             second_parse: self.second_parse,
@@ -79,17 +74,10 @@ pub struct KeepNone;
 
 impl<A, B> AndCombinator<A, B> for KeepNone {
     type Combined = ();
-    fn combine(&self, _: (A, B)) -> Self::Combined {
-        ()
-    }
+    fn combine(&self, _: (A, B)) -> Self::Combined {}
 }
 
-impl<A, B, C> From<(A, B, C)> for AndThenParser<A, B, C>
-where
-    A: Parser,
-    B: Parser,
-    C: AndCombinator<A::Output, B::Output>,
-{
+impl<A, B, C> From<(A, B, C)> for AndThenParser<A, B, C> {
     fn from((first_parse, second_parse, combinator): (A, B, C)) -> Self {
         Self {
             first_parse,
@@ -99,11 +87,7 @@ where
     }
 }
 
-impl<A, B> From<(A, B)> for AndThenParser<A, B, IdentityAndCombinator>
-where
-    A: Parser,
-    B: Parser,
-{
+impl<A, B> From<(A, B)> for AndThenParser<A, B, IdentityAndCombinator> {
     fn from((first_parse, second_parse): (A, B)) -> Self {
         Self {
             first_parse,
@@ -122,8 +106,33 @@ where
 {
     type Output = C::Combined;
     fn parse(&self, input: &str) -> ParserRes<Self::Output> {
-        let (a, rest) = A::parse(&self.first_parse, input)?;
-        let (b, rest) = B::parse(&self.second_parse, &rest)?;
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (a, rest) = A::parse_stateful(&self.first_parse, input, state)?;
+        let (b, rest) = B::parse_stateful(&self.second_parse, &rest, state)?;
         Ok((C::combine(&self.combinator, (a, b)), rest))
     }
+
+    fn describe(&self) -> Description {
+        self.first_parse
+            .describe()
+            .then(self.second_parse.describe())
+    }
+}
+
+impl<K, A, B, C> StreamParser<K> for AndThenParser<A, B, C>
+where
+    A: StreamParser<K>,
+    B: StreamParser<K>,
+    C: AndCombinator<A::Output, B::Output>,
+    C::Combined: Debug,
+{
+    type Output = C::Combined;
+    fn parse_tokens<'t>(&self, tokens: &'t [Token<K>]) -> TokenRes<'t, K, Self::Output> {
+        let (a, rest) = self.first_parse.parse_tokens(tokens)?;
+        let (b, rest) = self.second_parse.parse_tokens(rest)?;
+        Ok((self.combinator.combine((a, b)), rest))
+    }
 }
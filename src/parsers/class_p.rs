@@ -0,0 +1,374 @@
+//! Sets of characters, which unlike `fn` pointers can be built from data.
+//!
+//! A `CharSet` is made from some characters (`one_of`, `none_of`), from ranges such as
+//! `'a'..='z'`, or from the Unicode identifier classes (`xid_start`, `xid_continue`, with the
+//! `unicode` feature). Sets can be combined with `union`, `difference` and `complement`.
+//! Whatever a set is made of, ASCII characters are looked up in a bitmap.
+//!
+//! A set parses one character that it contains, and `predicate` turns it into a closure for
+//! `ParseIf`, `ParseWhile` and `ParseWhileOrNothing`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::parsers::{class_p::{one_of, CharSet}, ParseWhile};
+//! use mini_parc::traits::Parser;
+//!
+//! let operator = one_of("+-*/");
+//! assert_eq!(operator.parse("*2"), Ok(('*', "2".to_string())));
+//!
+//! let hex = CharSet::from('0'..='9').union('a'..='f').union('A'..='F');
+//! assert_eq!(hex.to_string(), "[0-9A-Fa-f]");
+//! let hex_digits = ParseWhile(hex.predicate());
+//! assert_eq!(hex_digits.parse("fF0 x"), Ok(("fF0".to_string(), " x".to_string())));
+//!
+//! let consonant = CharSet::from('a'..='z').difference(one_of("aeiou"));
+//! assert!(consonant.contains('b') && !consonant.contains('e'));
+//! ```
+
+use std::{fmt::Display, ops::RangeInclusive, rc::Rc};
+
+use crate::{
+    describe::{CharPredicate, Description},
+    errors::ParsingError,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// How a set is made, used for the characters past ASCII and to display the set
+enum Class {
+    /// Sorted ranges, which do not overlap or touch
+    Ranges(Vec<(char, char)>),
+    #[cfg(feature = "unicode")]
+    XidStart,
+    #[cfg(feature = "unicode")]
+    XidContinue,
+    Union(Rc<Class>, Rc<Class>),
+    Difference(Rc<Class>, Rc<Class>),
+    Complement(Rc<Class>),
+}
+
+impl Class {
+    fn contains(&self, c: char) -> bool {
+        match self {
+            Class::Ranges(ranges) => ranges
+                .binary_search_by(|&(lo, hi)| match (lo > c, hi < c) {
+                    (true, _) => std::cmp::Ordering::Greater,
+                    (_, true) => std::cmp::Ordering::Less,
+                    _ => std::cmp::Ordering::Equal,
+                })
+                .is_ok(),
+            #[cfg(feature = "unicode")]
+            Class::XidStart => unicode_ident::is_xid_start(c),
+            #[cfg(feature = "unicode")]
+            Class::XidContinue => unicode_ident::is_xid_continue(c),
+            Class::Union(a, b) => a.contains(c) || b.contains(c),
+            Class::Difference(a, b) => a.contains(c) && !b.contains(c),
+            Class::Complement(a) => !a.contains(c),
+        }
+    }
+
+    /// Write the class, with parentheses if it is made of two others
+    fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Class::Union(..) | Class::Difference(..) => write!(f, "({self})"),
+            _ => write!(f, "{self}"),
+        }
+    }
+}
+
+fn fmt_ranges(ranges: &[(char, char)], f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let escape = |c: char| match c {
+        '\\' | ']' | '[' | '-' | '^' => format!("\\{c}"),
+        c if c.is_control() => c.escape_debug().to_string(),
+        c => c.to_string(),
+    };
+    for &(lo, hi) in ranges {
+        match hi as u32 - lo as u32 {
+            0 => write!(f, "{}", escape(lo))?,
+            1 => write!(f, "{}{}", escape(lo), escape(hi))?,
+            _ => write!(f, "{}-{}", escape(lo), escape(hi))?,
+        }
+    }
+    Ok(())
+}
+
+impl Display for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Class::Ranges(ranges) => {
+                write!(f, "[")?;
+                fmt_ranges(ranges, f)?;
+                write!(f, "]")
+            }
+            #[cfg(feature = "unicode")]
+            Class::XidStart => write!(f, "XID_Start"),
+            #[cfg(feature = "unicode")]
+            Class::XidContinue => write!(f, "XID_Continue"),
+            Class::Union(a, b) => {
+                a.fmt_operand(f)?;
+                write!(f, " | ")?;
+                b.fmt_operand(f)
+            }
+            Class::Difference(a, b) => {
+                a.fmt_operand(f)?;
+                write!(f, " - ")?;
+                b.fmt_operand(f)
+            }
+            Class::Complement(a) => match a.as_ref() {
+                Class::Ranges(ranges) => {
+                    write!(f, "[^")?;
+                    fmt_ranges(ranges, f)?;
+                    write!(f, "]")
+                }
+                _ => {
+                    write!(f, "^")?;
+                    a.fmt_operand(f)
+                }
+            },
+        }
+    }
+}
+
+/// Sort ranges and merge the ones that overlap or touch
+fn normalize(mut ranges: Vec<(char, char)>) -> Vec<(char, char)> {
+    ranges.retain(|(lo, hi)| lo <= hi);
+    ranges.sort();
+    let mut merged: Vec<(char, char)> = Vec::with_capacity(ranges.len());
+    for (lo, hi) in ranges {
+        match merged.last_mut() {
+            Some(last) if lo as u32 <= last.1 as u32 + 1 => last.1 = last.1.max(hi),
+            _ => merged.push((lo, hi)),
+        }
+    }
+    merged
+}
+
+/// A set of characters, see the `class_p` module
+#[derive(Clone)]
+pub struct CharSet {
+    /// Which ASCII characters are in the set, one bit for each
+    ascii: u128,
+    class: Rc<Class>,
+}
+
+impl CharSet {
+    fn from_class(class: Class) -> Self {
+        let ascii = (0..128u8)
+            .filter(|&b| class.contains(b as char))
+            .fold(0, |bits, b| bits | 1 << b);
+        Self {
+            ascii,
+            class: Rc::new(class),
+        }
+    }
+
+    /// The set with no characters in it
+    pub fn empty() -> Self {
+        Self {
+            ascii: 0,
+            class: Rc::new(Class::Ranges(vec![])),
+        }
+    }
+
+    /// Whether a character is in the set
+    pub fn contains(&self, c: char) -> bool {
+        match c.is_ascii() {
+            true => self.ascii >> c as u32 & 1 == 1,
+            false => self.class.contains(c),
+        }
+    }
+
+    /// The characters that are in either set
+    pub fn union<S>(&self, other: S) -> Self
+    where
+        S: Into<CharSet>,
+    {
+        let other = other.into();
+        let class = match (self.class.as_ref(), other.class.as_ref()) {
+            (Class::Ranges(a), Class::Ranges(b)) => {
+                Rc::new(Class::Ranges(normalize([&a[..], &b[..]].concat())))
+            }
+            _ => Rc::new(Class::Union(self.class.clone(), other.class)),
+        };
+        Self {
+            ascii: self.ascii | other.ascii,
+            class,
+        }
+    }
+
+    /// The characters of this set that are not in the other one
+    pub fn difference<S>(&self, other: S) -> Self
+    where
+        S: Into<CharSet>,
+    {
+        let other = other.into();
+        Self {
+            ascii: self.ascii & !other.ascii,
+            class: Rc::new(Class::Difference(self.class.clone(), other.class)),
+        }
+    }
+
+    /// The characters that are not in the set
+    pub fn complement(&self) -> Self {
+        Self {
+            ascii: !self.ascii,
+            class: Rc::new(Class::Complement(self.class.clone())),
+        }
+    }
+
+    /// A closure that tests if a character is in the set, for `ParseIf` and the `ParseWhile`
+    /// family
+    pub fn predicate(&self) -> impl Fn(char) -> bool + Clone + 'static {
+        let set = self.clone();
+        move |c| set.contains(c)
+    }
+}
+
+impl Display for CharSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.class)
+    }
+}
+
+impl std::fmt::Debug for CharSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CharSet({self})")
+    }
+}
+
+impl From<RangeInclusive<char>> for CharSet {
+    fn from(range: RangeInclusive<char>) -> Self {
+        Self::from_class(Class::Ranges(normalize(vec![(
+            *range.start(),
+            *range.end(),
+        )])))
+    }
+}
+
+impl From<char> for CharSet {
+    fn from(c: char) -> Self {
+        Self::from(c..=c)
+    }
+}
+
+impl From<&str> for CharSet {
+    fn from(chars: &str) -> Self {
+        one_of(chars)
+    }
+}
+
+/// The set of the characters of a string
+pub fn one_of(chars: &str) -> CharSet {
+    CharSet::from_class(Class::Ranges(normalize(
+        chars.chars().map(|c| (c, c)).collect(),
+    )))
+}
+
+/// The set of every character that is not in a string
+pub fn none_of(chars: &str) -> CharSet {
+    one_of(chars).complement()
+}
+
+/// The characters that can start an identifier, the Unicode `XID_Start` property
+#[cfg(feature = "unicode")]
+pub fn xid_start() -> CharSet {
+    CharSet::from_class(Class::XidStart)
+}
+
+/// The characters that can continue an identifier, the Unicode `XID_Continue` property
+#[cfg(feature = "unicode")]
+pub fn xid_continue() -> CharSet {
+    CharSet::from_class(Class::XidContinue)
+}
+
+impl Parser for CharSet {
+    type Output = char;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        let Some(c) = input.chars().next() else {
+            return Err(ParsingError::CannotParseAnEmptyString);
+        };
+        if !self.contains(c) {
+            return Err(ParsingError::PatternNotFound(format!(
+                "{c:?} is not in {self}"
+            )));
+        }
+        Ok((c, input[c.len_utf8()..].to_string()))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Char(CharPredicate::new(self.to_string(), self.predicate()))
+    }
+}
+
+#[cfg(test)]
+mod class_parser_test {
+    use super::{none_of, one_of, CharSet};
+    use crate::errors::ParsingError;
+    use crate::parsers::{ParseWhile, ParseWhileOrNothing};
+    use crate::traits::Parser;
+
+    #[test]
+    fn sets_and_operations() {
+        let set = one_of("+-*/");
+        assert!(set.contains('-') && !set.contains('%'));
+        assert_eq!(set.to_string(), r"[*+\-/]");
+        assert_eq!(
+            set.parse("%"),
+            Err(ParsingError::PatternNotFound(
+                r"'%' is not in [*+\-/]".to_string()
+            ))
+        );
+        assert_eq!(set.parse(""), Err(ParsingError::CannotParseAnEmptyString));
+
+        let not_quote = none_of("\"\\");
+        assert!(not_quote.contains('é') && !not_quote.contains('"'));
+        assert_eq!(not_quote.to_string(), r#"[^"\\]"#);
+
+        // Ranges are merged, and the bitmap agrees with the ranges past ASCII
+        let letters = CharSet::from('a'..='m').union('n'..='z').union('α'..='ω');
+        assert_eq!(letters.to_string(), "[a-zα-ω]");
+        assert!(letters.contains('q') && letters.contains('λ') && !letters.contains('A'));
+
+        let no_vowels = letters.difference("aeiouα");
+        assert_eq!(no_vowels.to_string(), "[a-zα-ω] - [aeiouα]");
+        assert!(no_vowels.contains('b') && no_vowels.contains('β'));
+        assert!(!no_vowels.contains('a') && !no_vowels.contains('α'));
+        assert!(no_vowels.complement().contains('a'));
+        assert!(!CharSet::empty().contains('\0'));
+        assert!(CharSet::empty().complement().contains('\u{10FFFF}'));
+    }
+
+    #[test]
+    fn composes_with_parse_while() {
+        let digits = CharSet::from('0'..='9').union('_');
+        let number = ParseWhile(digits.predicate());
+        assert_eq!(
+            number.parse("1_000 + 2"),
+            Ok(("1_000".to_string(), " + 2".to_string()))
+        );
+        let spaces = ParseWhileOrNothing(none_of("\n").difference(' '..='~').predicate());
+        assert_eq!(
+            spaces.parse("\t\u{a0}x"),
+            Ok(("\t\u{a0}".to_string(), "x".to_string()))
+        );
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn identifier_classes() {
+        use super::{xid_continue, xid_start};
+
+        let start = xid_start().union('_');
+        assert_eq!(start.to_string(), "XID_Start | [_]");
+        assert!(start.contains('é') && start.contains('_') && !start.contains('1'));
+        assert!(xid_continue().contains('1') && xid_continue().contains('\u{301}'));
+        assert!(!xid_start().contains('_'));
+
+        let ident = start.and_then(ParseWhileOrNothing(xid_continue().predicate()));
+        assert_eq!(
+            ident.parse("naïve_1 = 2"),
+            Ok((('n', "aïve_1".to_string()), " = 2".to_string()))
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{
+    describe::Description, errors::ParsingError, state::ParseState, traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// The output of one of two parsers, see `Parser::or_either`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
+impl<A, B> Either<A, B> {
+    /// Turn either side into a common type
+    pub fn either<T, F, G>(self, f: F, g: G) -> T
+    where
+        F: FnOnce(A) -> T,
+        G: FnOnce(B) -> T,
+    {
+        match self {
+            Either::Left(a) => f(a),
+            Either::Right(b) => g(b),
+        }
+    }
+
+    pub fn map_left<T, F>(self, f: F) -> Either<T, B>
+    where
+        F: FnOnce(A) -> T,
+    {
+        match self {
+            Either::Left(a) => Either::Left(f(a)),
+            Either::Right(b) => Either::Right(b),
+        }
+    }
+
+    pub fn map_right<T, F>(self, f: F) -> Either<A, T>
+    where
+        F: FnOnce(B) -> T,
+    {
+        match self {
+            Either::Left(a) => Either::Left(a),
+            Either::Right(b) => Either::Right(f(b)),
+        }
+    }
+}
+
+impl<T> Either<T, T> {
+    /// Get the value out, when both sides have the same type
+    pub fn into_inner(self) -> T {
+        match self {
+            Either::Left(t) | Either::Right(t) => t,
+        }
+    }
+}
+
+/// Like `OrThenParser`, but the two parsers may have different outputs. Like it, the second
+/// parser is not tried if the first one fails past an `expect`.
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{either_p::Either, ParseIf, ParseMatch};
+/// use mini_parc::traits::Parser;
+///
+/// let p = ParseIf(|c| c.is_numeric()).or_either(ParseMatch("none"));
+/// assert_eq!(p.parse("1"), Ok((Either::Left('1'), String::new())));
+/// assert_eq!(p.parse("none"), Ok((Either::Right("none".to_string()), String::new())));
+/// ```
+pub struct EitherParser<A, B> {
+    pub first_parse: A,
+    pub second_parse: B,
+}
+
+impl<A, B> Parser for EitherParser<A, B>
+where
+    A: Parser,
+    B: Parser,
+{
+    type Output = Either<A::Output, B::Output>;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let snapshot = state.snapshot();
+        let aerr = match self.first_parse.parse_stateful(input, state) {
+            Ok((a, rest)) => return Ok((Either::Left(a), rest)),
+            Err(e) if e.is_committed(input) => return Err(e),
+            Err(e) => e,
+        };
+        state.restore(snapshot);
+
+        let snapshot = state.snapshot();
+        let berr = match self.second_parse.parse_stateful(input, state) {
+            Ok((b, rest)) => return Ok((Either::Right(b), rest)),
+            Err(e) if e.is_committed(input) => return Err(e),
+            Err(e) => e,
+        };
+        state.restore(snapshot);
+
+        Err(ParsingError::of_choice(
+            [aerr, berr],
+            ParsingError::PatternNotFound(
+                "Either Parser didnt match either of the branches".to_string(),
+            ),
+        ))
+    }
+
+    fn describe(&self) -> Description {
+        self.first_parse.describe().or(self.second_parse.describe())
+    }
+}
+
+/// Generate an enum with one variant per alternative, the parser that picks the first
+/// matching alternative, and a function to build that parser
+macro_rules! one_of {
+    ($name:ident, $parser:ident, $func:ident, $n:literal, $($var:ident $field:ident),+) => {
+        #[doc = concat!("The output of one of ", $n, " parsers, see `", stringify!($func), "`")]
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum $name<$($var),+> {
+            $($var($var)),+
+        }
+
+        #[doc = concat!("Try ", $n, " parsers in order, and return the output of the first one")]
+        #[doc = "that succeeds. The parsers may all have different outputs."]
+        #[doc = ""]
+        #[doc = "Like `OrThenParser`, the parsers after one that fails past an `expect` are"]
+        #[doc = "not tried."]
+        pub struct $parser<$($var),+> {
+            $(pub $field: $var),+
+        }
+
+        #[doc = concat!("Make a `", stringify!($parser), "`")]
+        pub fn $func<$($var),+>($($field: $var),+) -> $parser<$($var),+>
+        where
+            $($var: Parser),+
+        {
+            $parser { $($field),+ }
+        }
+
+        impl<$($var),+> Parser for $parser<$($var),+>
+        where
+            $($var: Parser),+
+        {
+            type Output = $name<$($var::Output),+>;
+            fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+                self.parse_stateful(input, &mut ())
+            }
+
+            fn parse_stateful(
+                &self,
+                input: &str,
+                state: &mut dyn ParseState,
+            ) -> ParserRes<Self::Output> {
+                let mut errors = vec![];
+                $(
+                    let snapshot = state.snapshot();
+                    match self.$field.parse_stateful(input, state) {
+                        Ok((out, rest)) => return Ok(($name::$var(out), rest)),
+                        Err(e) if e.is_committed(input) => return Err(e),
+                        Err(e) => errors.push(e),
+                    }
+                    state.restore(snapshot);
+                )+
+
+                Err(ParsingError::of_choice(
+                    errors,
+                    ParsingError::PatternNotFound(format!(
+                        "did not match any of the {} branches",
+                        $n
+                    )),
+                ))
+            }
+
+            fn describe(&self) -> Description {
+                Description::Choice(vec![$(self.$field.describe()),+])
+            }
+        }
+    };
+}
+
+one_of!(OneOf3, OneOf3Parser, alt3, 3, A a, B b, C c);
+one_of!(OneOf4, OneOf4Parser, alt4, 4, A a, B b, C c, D d);
+one_of!(OneOf5, OneOf5Parser, alt5, 5, A a, B b, C c, D d, E e);
+one_of!(OneOf6, OneOf6Parser, alt6, 6, A a, B b, C c, D d, E e, F f);
+
+#[cfg(test)]
+mod either_parser_test {
+    use super::{alt3, Either, OneOf3};
+    use crate::parsers::{string_p::string_parser, ParseMatch, ParseWhile};
+    use crate::traits::Parser;
+
+    #[test]
+    fn either_then_combine() {
+        let number = ParseWhile(|c| c.is_numeric()).with_try_mapping(&|s| s.parse::<i64>().ok());
+        let p = number
+            .or_either(ParseMatch("true").value(true))
+            .with_mapping(&|e: Either<i64, bool>| e.either(|n| n != 0, |b| b));
+
+        assert_eq!(p.parse("0"), Ok((false, String::new())));
+        assert_eq!(p.parse("true"), Ok((true, String::new())));
+        assert!(p.parse("false").is_err());
+    }
+
+    #[test]
+    fn one_of_three() {
+        let p = alt3(
+            ParseWhile(|c| c.is_numeric()).with_try_mapping(&|s| s.parse::<u8>().ok()),
+            ParseMatch("null").value(()),
+            string_parser(),
+        );
+
+        assert_eq!(p.parse("12"), Ok((OneOf3::A(12), String::new())));
+        assert_eq!(p.parse("null"), Ok((OneOf3::B(()), String::new())));
+        assert_eq!(
+            p.parse("\"s\""),
+            Ok((OneOf3::C("s".to_string()), String::new()))
+        );
+        assert!(p.parse("x").is_err());
+    }
+}
@@ -0,0 +1,288 @@
+//! Identifiers and keywords.
+//!
+//! `identifier()` parses a character of its `start` set, followed by any number of its `rest`
+//! set (ASCII letters, digits and `_` by default, or the Unicode identifier classes with the
+//! `unicode` feature). Reserved words are rejected, and the identifiers are interned, so that
+//! every occurrence of a name shares the same `Rc<str>`.
+//!
+//! `Identifier::keyword` parses a reserved word, as long as it is not the start of a longer
+//! identifier.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::parsers::{ident_p::identifier, ParseMatch};
+//! use mini_parc::traits::Parser;
+//!
+//! let ident = identifier().reserved(["if", "else"]);
+//! let (name, rest) = ident.parse("iffy = 1").unwrap();
+//! assert_eq!((&*name, rest.as_str()), ("iffy", " = 1"));
+//! assert_eq!(
+//!     ident.parse("if x").unwrap_err().to_string(),
+//!     "`if` is a reserved keyword"
+//! );
+//!
+//! let condition = ident.keyword("if").and_then(ParseMatch(" ")).and_then(ident.clone());
+//! assert_eq!(&*condition.parse("if ready").unwrap().0 .1, "ready");
+//! assert!(condition.parse("iffy ready").is_err());
+//! ```
+
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use crate::{
+    describe::{CharPredicate, Description},
+    errors::ParsingError,
+    parsers::class_p::CharSet,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// A set of strings, where each string is only stored once. Clones share the same set.
+#[derive(Debug, Clone, Default)]
+pub struct Interner(Rc<RefCell<HashSet<Rc<str>>>>);
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The stored copy of a string, which is added if it is not in the set yet
+    pub fn intern(&self, s: &str) -> Rc<str> {
+        let mut strings = self.0.borrow_mut();
+        if let Some(interned) = strings.get(s) {
+            return interned.clone();
+        }
+        let interned: Rc<str> = s.into();
+        strings.insert(interned.clone());
+        interned
+    }
+
+    /// The number of different strings that were interned
+    pub fn len(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Parse an identifier, see the `ident_p` module
+#[derive(Debug, Clone)]
+pub struct Identifier {
+    /// The characters an identifier can start with
+    pub start: CharSet,
+    /// The characters that can follow the first one
+    pub rest: CharSet,
+    pub reserved: HashSet<String>,
+    pub interner: Interner,
+}
+
+impl Identifier {
+    /// Set the characters that an identifier can start with
+    pub fn start<S>(self, start: S) -> Self
+    where
+        S: Into<CharSet>,
+    {
+        Self {
+            start: start.into(),
+            ..self
+        }
+    }
+
+    /// Set the characters that can follow the first one
+    pub fn rest<S>(self, rest: S) -> Self
+    where
+        S: Into<CharSet>,
+    {
+        Self {
+            rest: rest.into(),
+            ..self
+        }
+    }
+
+    /// Use the Unicode identifier classes, `XID_Start` (and `_`) followed by `XID_Continue`
+    #[cfg(feature = "unicode")]
+    pub fn unicode(self) -> Self {
+        use crate::parsers::class_p::{xid_continue, xid_start};
+
+        self.start(xid_start().union('_')).rest(xid_continue())
+    }
+
+    /// Add words that are not identifiers
+    pub fn reserved<I, S>(mut self, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.reserved.extend(words.into_iter().map(Into::into));
+        self
+    }
+
+    /// Intern the identifiers with an interner that is shared with other parsers
+    pub fn interner(self, interner: Interner) -> Self {
+        Self { interner, ..self }
+    }
+
+    /// Parse a word, as long as it is not followed by a character that could continue an
+    /// identifier
+    pub fn keyword<S>(&self, word: S) -> Keyword
+    where
+        S: Into<String>,
+    {
+        Keyword {
+            word: word.into(),
+            rest: self.rest.clone(),
+        }
+    }
+}
+
+impl Parser for Identifier {
+    type Output = Rc<str>;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        let mut chars = input.char_indices();
+        match chars.next() {
+            Some((_, c)) if self.start.contains(c) => {}
+            Some((_, c)) => {
+                return Err(ParsingError::PatternNotFound(format!(
+                    "expected an identifier, found {c:?}"
+                )))
+            }
+            None => {
+                return Err(ParsingError::PatternNotFound(
+                    "expected an identifier, found the end of the input".to_string(),
+                ))
+            }
+        }
+        let end = chars
+            .find(|&(_, c)| !self.rest.contains(c))
+            .map_or(input.len(), |(i, _)| i);
+        let word = &input[..end];
+        if self.reserved.contains(word) {
+            return Err(ParsingError::PatternNotFound(format!(
+                "`{word}` is a reserved keyword"
+            )));
+        }
+        Ok((self.interner.intern(word), input[end..].to_string()))
+    }
+
+    fn describe(&self) -> Description {
+        let class =
+            |set: &CharSet| Description::Char(CharPredicate::new(set.to_string(), set.predicate()));
+        Description::named(
+            "identifier",
+            Description::Sequence(vec![
+                class(&self.start),
+                Description::Repeat {
+                    inner: Box::new(class(&self.rest)),
+                    min: 0,
+                },
+            ]),
+        )
+    }
+}
+
+/// An identifier made of ASCII letters, digits and `_`, which does not start with a digit
+pub fn identifier() -> Identifier {
+    let letters = CharSet::from('a'..='z').union('A'..='Z').union('_');
+    Identifier {
+        rest: letters.union('0'..='9'),
+        start: letters,
+        reserved: HashSet::new(),
+        interner: Interner::new(),
+    }
+}
+
+/// Parse a keyword, see `Identifier::keyword`
+#[derive(Debug, Clone)]
+pub struct Keyword {
+    pub word: String,
+    /// The characters that can not follow the keyword
+    pub rest: CharSet,
+}
+
+impl Parser for Keyword {
+    type Output = String;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        let Some(after) = input.strip_prefix(&self.word) else {
+            return Err(ParsingError::PatternNotFound(format!(
+                "expected keyword `{}`",
+                self.word
+            )));
+        };
+        match after.chars().next() {
+            Some(c) if self.rest.contains(c) => Err(ParsingError::PatternNotFound(format!(
+                "expected keyword `{}`, found a longer identifier",
+                self.word
+            ))),
+            _ => Ok((self.word.clone(), after.to_string())),
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::Literal(self.word.clone())
+    }
+}
+
+#[cfg(test)]
+mod ident_parser_test {
+    use std::rc::Rc;
+
+    use super::{identifier, Interner};
+    use crate::errors::ParsingError;
+    use crate::parsers::{class_p::one_of, repeat_p::RepeatParser, ParseWhileOrNothing};
+    use crate::traits::Parser;
+
+    #[test]
+    fn identifiers_and_reserved_words() {
+        let ident = identifier().reserved(["let", "fn"]);
+        assert_eq!(&*ident.parse("_x1+").unwrap().0, "_x1");
+        assert_eq!(&*ident.parse("letter").unwrap().0, "letter");
+        assert_eq!(
+            ident.parse("let x"),
+            Err(ParsingError::PatternNotFound(
+                "`let` is a reserved keyword".to_string()
+            ))
+        );
+        assert_eq!(
+            ident.parse("1x"),
+            Err(ParsingError::PatternNotFound(
+                "expected an identifier, found '1'".to_string()
+            ))
+        );
+        assert!(ident.parse("").is_err());
+
+        // Lisp style names
+        let ident = identifier().rest(one_of("-?!").union('a'..='z'));
+        assert_eq!(&*ident.parse("empty? x").unwrap().0, "empty?");
+
+        let kw = ident.keyword("fn");
+        assert_eq!(kw.parse("fn(x)"), Ok(("fn".to_string(), "(x)".to_string())));
+        assert!(kw.parse("fn-name").is_err());
+    }
+
+    #[test]
+    fn interned() {
+        let interner = Interner::new();
+        let ident = identifier().interner(interner.clone());
+        let space = ParseWhileOrNothing(|c| c == ' ');
+        let words = RepeatParser::new(ident.and_then(space))
+            .parse("a b a a")
+            .unwrap()
+            .0;
+        assert!(Rc::ptr_eq(&words[0].0, &words[2].0) && Rc::ptr_eq(&words[0].0, &words[3].0));
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn unicode_identifiers() {
+        let ident = identifier().unicode().reserved(["für"]);
+        assert_eq!(&*ident.parse("größe = 1").unwrap().0, "größe");
+        assert_eq!(&*ident.parse("_ñ2").unwrap().0, "_ñ2");
+        assert!(ident.parse("für").is_err() && ident.parse("2a").is_err());
+        assert!(identifier()
+            .parse("größe")
+            .is_ok_and(|(name, _)| &*name == "gr"));
+    }
+}
@@ -0,0 +1,430 @@
+use std::rc::Rc;
+
+use crate::{
+    describe::Description,
+    errors::ParsingError,
+    parsers::state_p::{downcast_state, missing_state},
+    position::Position,
+    state::ParseState,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// The state used by the indentation sensitive parsers.
+///
+/// It keeps the whole source (so that positions can be found), and a stack of the
+/// indentation levels of the blocks that are currently open. Indentation is measured in
+/// spaces.
+#[derive(Debug, Clone)]
+pub struct IndentState {
+    source: Rc<str>,
+    levels: Vec<usize>,
+}
+
+impl IndentState {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.into(),
+            levels: vec![0],
+        }
+    }
+
+    /// The position at which `rest` starts in the source
+    pub fn position(&self, rest: &str) -> Position {
+        Position::locate(&self.source, rest)
+    }
+
+    /// The indentation of the innermost open block
+    pub fn level(&self) -> usize {
+        self.levels.last().copied().unwrap_or(0)
+    }
+
+    fn at_line_start(&self, rest: &str) -> bool {
+        let offset = self.source.len().saturating_sub(rest.len());
+        offset == 0 || self.source.as_bytes()[offset - 1] == b'\n'
+    }
+}
+
+/// Run a parser that uses the indentation sensitive parsers (such as `indented_block`)
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::indent_p::{indented_block, parse_indented, same_indent};
+/// use mini_parc::parsers::{ParseMatch, ParseWhile};
+/// use mini_parc::traits::Parser;
+///
+/// let word = || ParseWhile(|c| c.is_alphabetic());
+/// let p = same_indent(word().and_then(ParseMatch(":")))
+///     .and_then(indented_block(word()));
+///
+/// let (((key, _), values), _) = parse_indented(&p, "list:\n  a\n  b\n").unwrap();
+/// assert_eq!(key, "list");
+/// assert_eq!(values, vec!["a".to_string(), "b".to_string()]);
+///
+/// let err = parse_indented(&p, "list:\n  a\n b\n").unwrap_err();
+/// assert_eq!(err.to_string(), "inconsistent indentation at line 3");
+/// ```
+pub fn parse_indented<P>(parser: &P, input: &str) -> ParserRes<P::Output>
+where
+    P: Parser,
+{
+    parser.parse_with(input, &mut IndentState::new(input))
+}
+
+fn layout(state: &mut dyn ParseState) -> Result<&mut IndentState, ParsingError> {
+    downcast_state::<IndentState>(state)
+}
+
+fn inconsistent(layout: &IndentState, rest: &str) -> ParsingError {
+    ParsingError::PatternNotFound(format!(
+        "inconsistent indentation at line {}",
+        layout.position(rest).line
+    ))
+}
+
+/// Number of spaces at the start of the input
+fn indent_of(input: &str) -> usize {
+    input.len() - input.trim_start_matches(' ').len()
+}
+
+/// Skip any lines that only contain whitespace, the input must be at the start of a line
+fn skip_blank_lines(mut input: &str) -> &str {
+    loop {
+        let trimmed = input.trim_start_matches([' ', '\t', '\r']);
+        match trimmed.strip_prefix('\n') {
+            Some(next_line) => input = next_line,
+            None if trimmed.is_empty() => return trimmed,
+            None => return input,
+        }
+    }
+}
+
+/// Skip the rest of the current line (which must only be whitespace), including the line
+/// break. If the input is already at the start of a line, nothing is skipped.
+fn finish_line<'i>(layout: &IndentState, input: &'i str) -> Result<&'i str, ParsingError> {
+    if layout.at_line_start(input) {
+        return Ok(input);
+    }
+    let trimmed = input.trim_start_matches([' ', '\t', '\r']);
+    if trimmed.is_empty() {
+        return Ok(trimmed);
+    }
+    trimmed.strip_prefix('\n').ok_or_else(|| {
+        ParsingError::PatternNotFound(format!(
+            "expected end of line at {}",
+            layout.position(trimmed)
+        ))
+    })
+}
+
+/// Outputs the current column (starting at 1), without consuming any input
+pub struct Column;
+
+impl Parser for Column {
+    type Output = usize;
+    fn parse(&self, _: &str) -> ParserRes<Self::Output> {
+        Err(missing_state())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let column = layout(state)?.position(input).column;
+        Ok((column, input.to_string()))
+    }
+}
+
+pub fn column() -> Column {
+    Column
+}
+
+/// Outputs the current position, without consuming any input
+pub struct CurrentPosition;
+
+impl Parser for CurrentPosition {
+    type Output = Position;
+    fn parse(&self, _: &str) -> ParserRes<Self::Output> {
+        Err(missing_state())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let position = layout(state)?.position(input);
+        Ok((position, input.to_string()))
+    }
+}
+
+pub fn position() -> CurrentPosition {
+    CurrentPosition
+}
+
+/// Parse a line that must be indented at the same level as the current block.
+///
+/// Blank lines before it are skipped, and the end of the line is consumed after the inner
+/// parser is done.
+pub struct SameIndent<P>(pub P);
+
+impl<P> Parser for SameIndent<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, _: &str) -> ParserRes<Self::Output> {
+        Err(missing_state())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let layout = layout(state)?;
+        let line = skip_blank_lines(finish_line(layout, input)?);
+        let indent = indent_of(line);
+        if line.is_empty() || indent != layout.level() {
+            return Err(inconsistent(layout, line));
+        }
+
+        let (out, rest) = self.0.parse_stateful(&line[indent..], state)?;
+        let rest = finish_line(self::layout(state)?, &rest)?;
+        Ok((out, rest.to_string()))
+    }
+
+    fn describe(&self) -> Description {
+        self.0.describe()
+    }
+}
+
+pub fn same_indent<P>(parser: P) -> SameIndent<P>
+where
+    P: Parser,
+{
+    SameIndent(parser)
+}
+
+/// Parse one or more lines that are indented deeper than the current block, each line is
+/// parsed with the inner parser. The block ends at the first line that is indented less
+/// than its first line.
+///
+/// If the current line has not ended yet, the rest of it must be whitespace.
+pub struct IndentedBlock<P>(pub P);
+
+impl<P> IndentedBlock<P>
+where
+    P: Parser,
+{
+    fn parse_lines(
+        &self,
+        mut rest: String,
+        level: usize,
+        state: &mut dyn ParseState,
+    ) -> ParserRes<Vec<P::Output>> {
+        let mut acc = vec![];
+        loop {
+            let line = skip_blank_lines(&rest);
+            let indent = indent_of(line);
+            if line.is_empty() {
+                break;
+            }
+
+            let layout = layout(state)?;
+            if indent > level {
+                return Err(inconsistent(layout, line));
+            }
+            if indent < level {
+                // Closing this block must go back to the level of an enclosing block
+                if !layout.levels.contains(&indent) {
+                    return Err(inconsistent(layout, line));
+                }
+                break;
+            }
+
+            let (out, r) = self.0.parse_stateful(&line[indent..], state)?;
+            rest = finish_line(self::layout(state)?, &r)?.to_string();
+            acc.push(out);
+        }
+        Ok((acc, rest))
+    }
+}
+
+impl<P> Parser for IndentedBlock<P>
+where
+    P: Parser,
+{
+    type Output = Vec<P::Output>;
+    fn parse(&self, _: &str) -> ParserRes<Self::Output> {
+        Err(missing_state())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let layout = layout(state)?;
+        let first = skip_blank_lines(finish_line(layout, input)?);
+        let level = indent_of(first);
+        if first.is_empty() || level <= layout.level() {
+            return Err(ParsingError::PatternNotFound(format!(
+                "expected an indented block at line {}",
+                layout.position(first).line
+            )));
+        }
+
+        layout.levels.push(level);
+        let res = self.parse_lines(first.to_string(), level, state);
+        // The levels are restored on errors too, since the state may be reused after
+        // backtracking
+        if let Ok(layout) = self::layout(state) {
+            layout.levels.pop();
+        }
+        res
+    }
+
+    fn describe(&self) -> Description {
+        Description::Repeat {
+            inner: Box::new(self.0.describe()),
+            min: 1,
+        }
+    }
+}
+
+pub fn indented_block<P>(parser: P) -> IndentedBlock<P>
+where
+    P: Parser,
+{
+    IndentedBlock(parser)
+}
+
+/// Parse a logical line that may continue onto the next lines, as long as they are indented
+/// deeper than the current block.
+///
+/// The lines are folded into one, joined by a single space, and the inner parser must consume
+/// all of the folded line. Since the inner parser runs on the folded line, positions found
+/// inside of it are not meaningful.
+pub struct LineFold<P>(pub P);
+
+impl<P> Parser for LineFold<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, _: &str) -> ParserRes<Self::Output> {
+        Err(missing_state())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let layout = layout(state)?;
+        let level = layout.level();
+
+        // Find the end of the logical line
+        let mut end = input.find('\n').unwrap_or(input.len());
+        while end < input.len() {
+            let next = &input[end + 1..];
+            let next_end = next.find('\n').unwrap_or(next.len());
+            if next[..next_end].trim().is_empty() || indent_of(next) <= level {
+                break;
+            }
+            end += 1 + next_end;
+        }
+
+        let folded = input[..end]
+            .lines()
+            .map(|l| l.trim())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let (out, unparsed) = self.0.parse_stateful(&folded, state)?;
+        if !unparsed.trim().is_empty() {
+            return Err(ParsingError::PatternNotFound(format!(
+                "unexpected \"{}\" in folded line",
+                unparsed.trim()
+            )));
+        }
+        Ok((out, input[end..].to_string()))
+    }
+
+    fn describe(&self) -> Description {
+        self.0.describe()
+    }
+}
+
+pub fn line_fold<P>(parser: P) -> LineFold<P>
+where
+    P: Parser,
+{
+    LineFold(parser)
+}
+
+#[cfg(test)]
+mod indent_parser_test {
+    use super::{column, indented_block, line_fold, parse_indented, same_indent};
+    use crate::parsers::and_p::{KeepFirstOutputOnly, KeepSecondOutputOnly};
+    use crate::parsers::repeat_p::RepeatParser;
+    use crate::parsers::rule_p::{recursive, Rule};
+    use crate::parsers::{ParseMatch, ParseWhile, ParseWhileOrNothing};
+    use crate::traits::Parser;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Node(String, Vec<Node>);
+
+    fn tree<'a>() -> Rule<'a, Node> {
+        // node = word (":" block)?
+        recursive(|node: Rule<'a, Node>| {
+            let children = ParseMatch(":")
+                .and_then(indented_block(node))
+                .combine(KeepSecondOutputOnly)
+                .or_default();
+            ParseWhile(|c| c.is_alphanumeric())
+                .and_then(children)
+                .with_mapping(&|(name, children)| Node(name, children))
+        })
+    }
+
+    fn leaf(name: &str) -> Node {
+        Node(name.to_string(), vec![])
+    }
+
+    #[test]
+    fn nested_blocks() {
+        let doc = RepeatParser::new(same_indent(tree()));
+        let input = "root:\n  a\n\n  b:\n    c\n  d\nother\n";
+        let (nodes, _) = parse_indented(&doc, input).unwrap();
+        assert_eq!(
+            nodes,
+            vec![
+                Node(
+                    "root".to_string(),
+                    vec![leaf("a"), Node("b".to_string(), vec![leaf("c")]), leaf("d")]
+                ),
+                leaf("other"),
+            ]
+        );
+    }
+
+    #[test]
+    fn indentation_errors() {
+        let word = || ParseWhile(|c| c.is_alphanumeric());
+        let doc = same_indent(word().and_then(ParseMatch(":"))).and_then(indented_block(word()));
+
+        let err = parse_indented(&doc, "root:\n    a\n  b\n").unwrap_err();
+        assert_eq!(err.to_string(), "inconsistent indentation at line 3");
+
+        let err = parse_indented(&doc, "root:\n  a\n    b\n").unwrap_err();
+        assert_eq!(err.to_string(), "inconsistent indentation at line 3");
+
+        let err = parse_indented(&doc, "root:\nb\n").unwrap_err();
+        assert_eq!(err.to_string(), "expected an indented block at line 2");
+    }
+
+    #[test]
+    fn folded_lines() {
+        let words = RepeatParser::new(
+            ParseWhile(|c| c.is_alphabetic())
+                .and_then(ParseWhileOrNothing(|c| c == ' '))
+                .combine(KeepFirstOutputOnly),
+        );
+        let p = same_indent(line_fold(words)).and_then(same_indent(ParseMatch("end")));
+
+        let ((words, end), rest) = parse_indented(&p, "a b\n  c\n   d\nend").unwrap();
+        assert_eq!(words, vec!["a", "b", "c", "d"]);
+        assert_eq!(end, "end");
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn current_column() {
+        let p = ParseMatch("ab\ncd").and_then(column());
+        assert_eq!(parse_indented(&p, "ab\ncd").unwrap().0 .1, 3);
+        assert!(p.parse("ab\ncd").is_err());
+    }
+}
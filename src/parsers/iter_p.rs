@@ -0,0 +1,139 @@
+use crate::{errors::ParsingError, state::ParseState, traits::Parser};
+
+/// Lazily applies a parser over and over to an input, see `Parser::iter` and
+/// `Parser::iter_with`.
+///
+/// Each successful parse is yielded as `Ok`. Once all of the input is consumed, the iterator
+/// ends. If the parser fails (or succeeds without consuming anything) before that, the error
+/// is yielded, and then the iterator ends. Either way, what was left of the input can be
+/// found with `remainder`.
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{and_p::KeepFirstOutputOnly, ParseMatch, ParseWhile};
+/// use mini_parc::traits::Parser;
+///
+/// let record = ParseWhile(|c| c.is_alphanumeric())
+///     .and_then(ParseMatch("\n"))
+///     .combine(KeepFirstOutputOnly);
+///
+/// let mut records = record.iter("a\nb\n!!");
+/// assert_eq!(records.next(), Some(Ok("a".to_string())));
+/// assert_eq!(records.next(), Some(Ok("b".to_string())));
+/// assert!(matches!(records.next(), Some(Err(_))));
+/// assert_eq!(records.next(), None);
+/// assert_eq!(records.remainder(), "!!");
+/// ```
+pub struct ParseIter<'p, 's, P> {
+    parser: &'p P,
+    rest: String,
+    done: bool,
+    state: Option<&'s mut dyn ParseState>,
+}
+
+impl<'p, 's, P> ParseIter<'p, 's, P> {
+    pub fn new(parser: &'p P, input: &str) -> Self {
+        Self {
+            parser,
+            rest: input.to_string(),
+            done: false,
+            state: None,
+        }
+    }
+
+    /// Pass the user state to the parser every time it is run, see `Parser::parse_with`
+    pub fn with_state(mut self, state: &'s mut dyn ParseState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// The part of the input that has not been parsed yet
+    pub fn remainder(&self) -> &str {
+        &self.rest
+    }
+
+    /// Stop iterating, and get the part of the input that has not been parsed yet
+    pub fn into_remainder(self) -> String {
+        self.rest
+    }
+}
+
+impl<'p, 's, P> Iterator for ParseIter<'p, 's, P>
+where
+    P: Parser,
+{
+    type Item = Result<P::Output, ParsingError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.rest.is_empty() {
+            return None;
+        }
+
+        let parsed = match &mut self.state {
+            Some(state) => self.parser.parse_stateful(&self.rest, *state),
+            None => self.parser.parse(&self.rest),
+        };
+        match parsed {
+            Ok((out, rest)) if rest.len() < self.rest.len() => {
+                self.rest = rest;
+                Some(Ok(out))
+            }
+            Ok(_) => {
+                self.done = true;
+                Some(Err(ParsingError::PatternNotFound(
+                    "parser did not consume any input".to_string(),
+                )))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod parse_iter_test {
+    use crate::parsers::and_p::KeepFirstOutputOnly;
+    use crate::parsers::{ParseMatch, ParseWhile, ParseWhileOrNothing};
+    use crate::traits::Parser;
+
+    #[test]
+    fn lazy_records() {
+        let record = ParseWhile(|c| c.is_numeric())
+            .with_try_mapping(&|s| s.parse::<u32>().ok())
+            .and_then(ParseMatch(";"))
+            .combine(KeepFirstOutputOnly);
+
+        let mut records = record.iter("1;22;333;");
+        let total: u32 = records.by_ref().map(|r| r.unwrap()).sum();
+        assert_eq!(total, 356);
+        assert_eq!(records.remainder(), "");
+
+        let records = record.iter("1;x;2;");
+        let (ok, err): (Vec<_>, Vec<_>) = records.partition(|r| r.is_ok());
+        assert_eq!(ok.len(), 1);
+        assert_eq!(err.len(), 1);
+    }
+
+    #[test]
+    fn no_progress() {
+        let nothing = ParseWhileOrNothing(|c| c.is_numeric());
+        let mut records = nothing.iter("abc");
+        assert!(matches!(records.next(), Some(Err(_))));
+        assert!(records.next().is_none());
+        assert_eq!(records.into_remainder(), "abc");
+    }
+
+    #[test]
+    fn state_is_passed_through() {
+        let record = ParseWhile(|c| c.is_numeric())
+            .with_state_mapping(&|digits, seen: &mut Vec<String>| seen.push(digits))
+            .and_then(ParseMatch(";"));
+
+        let mut seen: Vec<String> = vec![];
+        assert_eq!(record.iter_with("1;22;", &mut seen).count(), 2);
+        assert_eq!(seen, vec!["1".to_string(), "22".to_string()]);
+        assert!(matches!(record.iter("1;").next(), Some(Err(_))));
+    }
+}
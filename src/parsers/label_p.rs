@@ -0,0 +1,202 @@
+use crate::{
+    describe::Description, errors::ParsingError, state::ParseState, traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// If the inner parser fails, replace its error with `ParsingError::Expected(label)`.
+///
+/// This is useful to hide low level errors (such as "if predicate not met") behind a human
+/// readable name of the rule that failed. If the error has context frames (see
+/// `ContextParser`), they are kept, and only the error at the root is replaced.
+pub struct LabelParser<P> {
+    pub parser: P,
+    pub label: String,
+}
+
+impl<P> Parser for LabelParser<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        self.parser.parse_stateful(input, state).map_err(|e| {
+            let error = Box::new(ParsingError::Expected(self.label.clone()));
+            match e {
+                ParsingError::Context { stack, .. } => ParsingError::Context { error, stack },
+                _ => *error,
+            }
+        })
+    }
+
+    fn describe(&self) -> Description {
+        Description::named(self.label.clone(), self.parser.describe())
+    }
+}
+
+/// Like `LabelParser`, but the error remembers where it happened, so that
+/// `ParsingError::locate` (which `Parser::parse_complete` calls) can give its position.
+///
+/// Once this parser has failed further into the input than where a choice (`otherwise`,
+/// `optional`, ...) or a repetition started, they fail with its error instead of
+/// backtracking, since something was already matched that can only be followed by `label`.
+/// Errors that happened further into the input than this parser started are kept as they
+/// are.
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{repeat_p::RepeatParser, ParseIf, ParseMatch};
+/// use mini_parc::traits::Parser;
+///
+/// // After a ',', a digit is required
+/// let digit = || ParseIf(|c| c.is_ascii_digit());
+/// let more = ParseMatch(",").and_then(digit().expect("a digit"));
+/// let list = digit().and_then(RepeatParser::new(more).optional());
+///
+/// assert!(list.parse_complete("1,2").is_ok());
+/// assert_eq!(
+///     list.parse_complete("1,2,x").unwrap_err().to_string(),
+///     "expected a digit at line 1, column 5"
+/// );
+/// ```
+pub struct ExpectParser<P> {
+    pub parser: P,
+    pub label: String,
+}
+
+impl<P> Parser for ExpectParser<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        self.parser.parse_stateful(input, state).map_err(|e| {
+            if e.is_committed(input) {
+                return e;
+            }
+            let error = Box::new(ParsingError::Unlocated {
+                error: Box::new(ParsingError::Expected(self.label.clone())),
+                remaining: input.len(),
+            });
+            match e {
+                ParsingError::Context { stack, .. } => ParsingError::Context { error, stack },
+                _ => *error,
+            }
+        })
+    }
+
+    fn describe(&self) -> Description {
+        Description::named(self.label.clone(), self.parser.describe())
+    }
+}
+
+/// If the inner parser fails, push a context frame onto its error.
+///
+/// Frames are kept in a stack, so nesting these parsers gives errors such as:
+/// `expected number, in array element, in object value`
+pub struct ContextParser<P> {
+    pub parser: P,
+    pub frame: String,
+}
+
+impl<P> Parser for ContextParser<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        self.parser
+            .parse_stateful(input, state)
+            .map_err(|e| e.with_context(self.frame.clone()))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+#[cfg(test)]
+mod label_parser_test {
+    use crate::errors::ParsingError;
+    use crate::parsers::{ParseIf, ParseMatch, ParseWhile};
+    use crate::traits::Parser;
+
+    #[test]
+    fn label_replaces_error() {
+        let p = ParseIf(|c| c.is_numeric()).label("digit");
+        assert_eq!(p.parse("1a"), Ok(('1', "a".to_string())));
+        assert_eq!(
+            p.parse("a1"),
+            Err(ParsingError::Expected("digit".to_string()))
+        );
+    }
+
+    #[test]
+    fn label_keeps_context() {
+        let element = ParseIf(|c| c.is_numeric()).context("in array element");
+        let p = ParseMatch("[").and_then(element).label("array");
+
+        let err = p.parse("[x]").unwrap_err();
+        assert_eq!(err.root(), &ParsingError::Expected("array".to_string()));
+        assert_eq!(err.to_string(), "expected array, in array element");
+    }
+
+    #[test]
+    fn expect_commits() {
+        let array = ParseMatch("[")
+            .and_then(ParseIf(|c| c.is_numeric()).expect("number"))
+            .and_then(ParseMatch("]").expect("']'"))
+            .recognize()
+            .context("in array");
+        let p = array.otherwise(ParseMatch("[x]"));
+
+        // Past the `[`, the other branch is not tried
+        assert_eq!(
+            p.parse_complete("[x]").unwrap_err().to_string(),
+            "expected number at line 1, column 2, in array"
+        );
+        assert_eq!(
+            p.parse_complete("[1 ").unwrap_err().to_string(),
+            "expected ']' at line 1, column 3, in array"
+        );
+        // Failing where it started does not commit
+        let p = ParseWhile(|c| c.is_numeric())
+            .expect("number")
+            .otherwise(ParseMatch("x"));
+        assert_eq!(p.parse("x"), Ok(("x".to_string(), String::new())));
+        assert_eq!(
+            p.parse_complete("y").unwrap_err().to_string(),
+            "expected number at line 1, column 1"
+        );
+    }
+
+    #[test]
+    fn context_stack() {
+        let p = ParseIf(|c| c.is_numeric())
+            .label("number")
+            .context("in array element");
+        let p = ParseMatch("[")
+            .and_then(p)
+            .context("in object value for key \"port\"");
+
+        let err = p.parse("[x]").unwrap_err();
+        assert_eq!(err.root(), &ParsingError::Expected("number".to_string()));
+        assert_eq!(err.context_stack().len(), 2);
+        assert_eq!(
+            err.to_string(),
+            "expected number, in array element, in object value for key \"port\""
+        );
+    }
+}
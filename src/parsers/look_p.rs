@@ -0,0 +1,177 @@
+use crate::{
+    describe::Description, errors::ParsingError, state::ParseState, traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// Run a parser without consuming any input. If the inner parser fails, so does this one.
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{look_p::peek, ParseMatch};
+/// use mini_parc::traits::Parser;
+///
+/// let answer = peek(ParseMatch("if")).parse("if x");
+/// assert_eq!(answer, Ok(("if".to_string(), "if x".to_string())));
+/// ```
+pub struct Peek<P>(pub P);
+
+impl<P> Parser for Peek<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let snapshot = state.snapshot();
+        let res = self.0.parse_stateful(input, state);
+        state.restore(snapshot);
+        res.map(|(out, _)| (out, input.to_string()))
+    }
+
+    fn describe(&self) -> Description {
+        Description::LookAhead {
+            inner: Box::new(self.0.describe()),
+            negated: false,
+        }
+    }
+}
+
+pub fn peek<P>(parser: P) -> Peek<P>
+where
+    P: Parser,
+{
+    Peek(parser)
+}
+
+/// Succeed, without consuming any input, only if the inner parser fails.
+///
+/// # Example
+///
+/// Parse a keyword, as long as it is not the start of a longer word
+///
+/// ```rust
+/// use mini_parc::parsers::{look_p::not, ParseIf, ParseMatch};
+/// use mini_parc::traits::Parser;
+///
+/// let keyword = ParseMatch("if").and_then(not(ParseIf(|c| c.is_alphanumeric())));
+/// assert!(keyword.parse("if x").is_ok());
+/// assert!(keyword.parse("iffy").is_err());
+/// ```
+pub struct Not<P>(pub P);
+
+impl<P> Parser for Not<P>
+where
+    P: Parser,
+{
+    type Output = ();
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let snapshot = state.snapshot();
+        let res = self.0.parse_stateful(input, state);
+        state.restore(snapshot);
+        match res {
+            Ok((out, _)) => Err(ParsingError::PatternNotFound(format!("unexpected {out:?}"))),
+            Err(_) => Ok(((), input.to_string())),
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::LookAhead {
+            inner: Box::new(self.0.describe()),
+            negated: true,
+        }
+    }
+}
+
+pub fn not<P>(parser: P) -> Not<P>
+where
+    P: Parser,
+{
+    Not(parser)
+}
+
+/// Succeed only if there is no input left
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{look_p::eof, ParseMatch};
+/// use mini_parc::traits::Parser;
+///
+/// let p = ParseMatch("true").and_then(eof());
+/// assert!(p.parse("true").is_ok());
+/// assert!(p.parse("true garbage").is_err());
+/// ```
+pub struct Eof;
+
+impl Parser for Eof {
+    type Output = ();
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        match input.chars().next() {
+            None => Ok(((), String::new())),
+            Some(c) => Err(ParsingError::PatternNotFound(format!(
+                "expected end of input, found {c:?}"
+            ))),
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::End
+    }
+}
+
+pub fn eof() -> Eof {
+    Eof
+}
+
+#[cfg(test)]
+mod look_parser_test {
+    use super::{eof, not, peek};
+    use crate::errors::ParsingError;
+    use crate::parsers::{ParseIf, ParseMatch};
+    use crate::position::Position;
+    use crate::traits::Parser;
+
+    #[test]
+    fn lookahead() {
+        let p = peek(ParseMatch("a")).and_then(ParseMatch("ab"));
+        assert!(p.parse("ab").is_ok());
+        assert!(peek(ParseMatch("a")).parse("b").is_err());
+
+        let p = not(ParseMatch("-")).and_then(ParseIf(|c| c.is_ascii_punctuation()));
+        assert_eq!(p.parse("+1"), Ok((((), '+'), "1".to_string())));
+        assert!(p.parse("-1").is_err());
+
+        assert_eq!(eof().parse(""), Ok(((), String::new())));
+        assert!(eof().parse(" ").is_err());
+    }
+
+    #[test]
+    fn parse_complete() {
+        let p = ParseMatch("true");
+        assert_eq!(p.parse_complete("true"), Ok("true".to_string()));
+        assert_eq!(
+            p.parse_complete("true\n garbage"),
+            Err(ParsingError::UnconsumedInput {
+                found: '\n',
+                position: Position {
+                    offset: 4,
+                    line: 1,
+                    column: 5
+                }
+            })
+        );
+        assert_eq!(
+            p.parse_complete("true garbage").unwrap_err().to_string(),
+            "unexpected ' ' at line 1, column 5"
+        );
+        assert!(p.parse_complete("false").is_err());
+    }
+}
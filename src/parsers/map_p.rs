@@ -1,6 +1,13 @@
 use std::fmt::Debug;
 
-use crate::{errors::ParsingError, traits::Parser, type_alias::ParserRes};
+use crate::{
+    describe::Description,
+    errors::ParsingError,
+    lexer::Token,
+    state::ParseState,
+    traits::{Parser, StreamParser},
+    type_alias::{ParserRes, TokenRes},
+};
 
 /// Given a parser with output of type K, and a mapping K -> Z,
 /// make a new parser with output of type Z
@@ -9,11 +16,34 @@ pub struct MapParser<'a, P: Parser, T> {
     pub mapping: &'a dyn Fn(P::Output) -> T,
 }
 
+/// Given a parser of tokens with output of type O, and a mapping O -> T, make a new parser of
+/// tokens with output of type T, see `StreamParser::with_mapping`
+pub struct TokenMapParser<'a, P, O, T> {
+    pub parser: P,
+    pub mapping: &'a dyn Fn(O) -> T,
+}
+
 pub struct TryMapParser<'a, P: Parser, T> {
     pub parser: P,
     pub try_map: &'a dyn Fn(P::Output) -> Option<T>,
 }
 
+/// Run a parser, and output the part of the input that it consumed, instead of its output
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{ParseMatch, ParseWhile};
+/// use mini_parc::traits::Parser;
+///
+/// let float = ParseWhile(|c| c.is_numeric())
+///     .and_then(ParseMatch("."))
+///     .and_then(ParseWhile(|c| c.is_numeric()))
+///     .recognize();
+/// assert_eq!(float.parse("1.25x"), Ok(("1.25".to_string(), "x".to_string())));
+/// ```
+pub struct RecognizeParser<P>(pub P);
+
 impl<'a, P, T> Parser for MapParser<'a, P, T>
 where
     P: Parser,
@@ -21,7 +51,36 @@ where
 {
     type Output = T;
     fn parse(&self, input: &str) -> ParserRes<Self::Output> {
-        self.parser.parse_and_then_map(input, &self.mapping)
+        self.parser.parse_and_then_map(input, self.mapping)
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (p, rest) = self.parser.parse_stateful(input, state)?;
+        Ok(((self.mapping)(p), rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+impl<P> Parser for RecognizeParser<P>
+where
+    P: Parser,
+{
+    type Output = String;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (_, rest) = self.0.parse_stateful(input, state)?;
+        let consumed = input.len().saturating_sub(rest.len());
+        Ok((input[..consumed].to_string(), rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.0.describe()
     }
 }
 
@@ -32,10 +91,30 @@ where
 {
     type Output = T;
     fn parse(&self, input: &str) -> ParserRes<Self::Output> {
-        let (p, rest) = self.parser.parse(input)?;
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (p, rest) = self.parser.parse_stateful(input, state)?;
         match (self.try_map)(p) {
             None => Err(ParsingError::MappingError("mapping failed".to_string())),
             Some(mapped_val) => Ok((mapped_val, rest)),
         }
     }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+impl<'a, K, P, O, T> StreamParser<K> for TokenMapParser<'a, P, O, T>
+where
+    P: StreamParser<K, Output = O>,
+    T: Debug,
+{
+    type Output = T;
+    fn parse_tokens<'t>(&self, tokens: &'t [Token<K>]) -> TokenRes<'t, K, Self::Output> {
+        let (out, rest) = self.parser.parse_tokens(tokens)?;
+        Ok(((self.mapping)(out), rest))
+    }
 }
@@ -0,0 +1,326 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{Debug, Display},
+    ops::{Index, IndexMut},
+};
+
+use crate::{
+    describe::Description, errors::ParsingError, state::ParseState, traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// Cached result of running a rule at some position, the rest of the input is stored
+/// as its length, so that it can be sliced back out of the input
+type MemoEntry<T> = Result<(T, usize), ParsingError>;
+
+/// Rules registered by name, with some data about each of them. Parsers registered with
+/// the same name are given the same id, which indexes their data. This is shared by the
+/// `MemoCache` and the `Profiler`.
+pub(crate) struct RuleRegistry<T> {
+    ids: HashMap<String, usize>,
+    entries: Vec<T>,
+}
+
+impl<T> Default for RuleRegistry<T> {
+    fn default() -> Self {
+        Self {
+            ids: HashMap::new(),
+            entries: vec![],
+        }
+    }
+}
+
+impl<T> RuleRegistry<T> {
+    /// Get the id of a rule, registering it with the data made by `new` if it had not been
+    /// seen before
+    pub(crate) fn id<F>(&mut self, name: String, new: F) -> usize
+    where
+        F: FnOnce(&str) -> T,
+    {
+        if let Some(&id) = self.ids.get(&name) {
+            return id;
+        }
+        let id = self.entries.len();
+        self.entries.push(new(&name));
+        self.ids.insert(name, id);
+        id
+    }
+
+    /// The data of every rule, in the order they were registered
+    pub(crate) fn entries(&self) -> &[T] {
+        &self.entries
+    }
+
+    pub(crate) fn entries_mut(&mut self) -> &mut [T] {
+        &mut self.entries
+    }
+}
+
+impl<T> Index<usize> for RuleRegistry<T> {
+    type Output = T;
+    fn index(&self, id: usize) -> &T {
+        &self.entries[id]
+    }
+}
+
+impl<T> IndexMut<usize> for RuleRegistry<T> {
+    fn index_mut(&mut self, id: usize) -> &mut T {
+        &mut self.entries[id]
+    }
+}
+
+/// Hit and miss counts of a memoized rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoStats {
+    pub rule: String,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl Display for MemoStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} hits, {} misses",
+            self.rule, self.hits, self.misses
+        )
+    }
+}
+
+/// A packrat cache shared between memoized parsers
+///
+/// Results are keyed by (rule id, position), where rules are identified by their name. This
+/// means that parsers that are rebuilt recursively (such as `primitive_parser()` in the json
+/// example) will still share their cached results, as long as they are memoized with the
+/// same name.
+///
+/// Positions are only meaningful within a single input, so the cache remembers the input it
+/// was last used on, and clears itself when a memoized parser is run on an input that does
+/// not end that one. `MemoCache::parse` clears it up front.
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{memo_p::MemoCache, ParseMatch};
+/// use mini_parc::traits::Parser;
+///
+/// let cache = MemoCache::new();
+/// let ab = || ParseMatch("a").and_then(ParseMatch("b")).memoize(&cache, "ab");
+/// let p = ab()
+///     .and_then(ParseMatch("c"))
+///     .otherwise(ab().and_then(ParseMatch("d")));
+///
+/// assert!(cache.parse(&p, "abd").is_ok());
+/// assert_eq!(cache.stats()[0].hits, 1);
+/// ```
+#[derive(Default)]
+pub struct MemoCache {
+    rules: RefCell<RuleRegistry<MemoStats>>,
+    table: RefCell<HashMap<(usize, usize), Box<dyn Any>>>,
+    /// The longest input seen since the cache was last cleared, every cached result is for
+    /// some suffix of it
+    input: RefCell<String>,
+}
+
+impl MemoCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove all of the cached results, but keep the statistics
+    pub fn clear(&self) {
+        self.table.borrow_mut().clear();
+        self.input.borrow_mut().clear();
+    }
+
+    /// Clear the cache if the input is not a suffix of the one its results are for
+    fn track(&self, input: &str) {
+        let mut current = self.input.borrow_mut();
+        if !current.ends_with(input) {
+            self.table.borrow_mut().clear();
+            *current = input.to_string();
+        }
+    }
+
+    /// Clear the cache, then run the parser on the input
+    pub fn parse<P>(&self, parser: &P, input: &str) -> ParserRes<P::Output>
+    where
+        P: Parser,
+    {
+        self.clear();
+        parser.parse(input)
+    }
+
+    /// Statistics for every memoized rule, in the order they were registered
+    pub fn stats(&self) -> Vec<MemoStats> {
+        self.rules.borrow().entries().to_vec()
+    }
+
+    /// Set all of the hit and miss counts back to zero
+    pub fn reset_stats(&self) {
+        for s in self.rules.borrow_mut().entries_mut() {
+            s.hits = 0;
+            s.misses = 0;
+        }
+    }
+}
+
+/// A parser whose results are stored in a `MemoCache`, so that running it again at the
+/// same position will not re-run the inner parser.
+///
+/// The cache does not know about user state, so when parsing with a state other than `()`
+/// (see `Parser::parse_with`) the inner parser is always run, and nothing is cached.
+pub struct MemoParser<'c, P> {
+    pub parser: P,
+    rule: usize,
+    cache: &'c MemoCache,
+}
+
+impl<'c, P> MemoParser<'c, P> {
+    pub fn new<S>(parser: P, cache: &'c MemoCache, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        let rule = cache.rules.borrow_mut().id(name.into(), |name| MemoStats {
+            rule: name.to_string(),
+            hits: 0,
+            misses: 0,
+        });
+        Self {
+            parser,
+            rule,
+            cache,
+        }
+    }
+}
+
+impl<'c, P> Parser for MemoParser<'c, P>
+where
+    P: Parser,
+    P::Output: Clone + 'static,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.cache.track(input);
+        let key = (self.rule, input.len());
+        let cached = self
+            .cache
+            .table
+            .borrow()
+            .get(&key)
+            .and_then(|entry| entry.downcast_ref::<MemoEntry<P::Output>>())
+            .cloned();
+
+        let mut stats = self.cache.rules.borrow_mut();
+        if let Some(entry) = cached {
+            stats[self.rule].hits += 1;
+            let rest = |rest_len: usize| input[input.len() - rest_len..].to_string();
+            return entry.map(|(out, rest_len)| (out, rest(rest_len)));
+        }
+        stats[self.rule].misses += 1;
+        // The inner parser may use the cache too
+        drop(stats);
+
+        let res = self.parser.parse(input);
+        let entry: MemoEntry<P::Output> = match &res {
+            Ok((out, rest)) => Ok((out.clone(), rest.len())),
+            Err(e) => Err(e.clone()),
+        };
+        self.cache.table.borrow_mut().insert(key, Box::new(entry));
+        res
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        // Combinators pass the unit state when they are run without a state
+        if state.is_unit() {
+            return self.parse(input);
+        }
+        self.parser.parse_stateful(input, state)
+    }
+
+    fn describe(&self) -> Description {
+        let name = self.cache.rules.borrow()[self.rule].rule.clone();
+        Description::named(name, self.parser.describe())
+    }
+}
+
+#[cfg(test)]
+mod memo_parser_test {
+    use std::cell::Cell;
+
+    use super::MemoCache;
+    use crate::parsers::ParseMatch;
+    use crate::traits::Parser;
+    use crate::type_alias::ParserRes;
+
+    /// Counts how many times it actually ran
+    struct Counting<'a>(&'a Cell<usize>);
+
+    impl<'a> Parser for Counting<'a> {
+        type Output = String;
+        fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+            self.0.set(self.0.get() + 1);
+            ParseMatch("ab").parse(input)
+        }
+    }
+
+    #[test]
+    fn shared_prefix_is_parsed_once() {
+        let runs = Cell::new(0);
+        let cache = MemoCache::new();
+        let p = Counting(&runs)
+            .memoize(&cache, "ab")
+            .and_then(ParseMatch("c"))
+            .otherwise(
+                Counting(&runs)
+                    .memoize(&cache, "ab")
+                    .and_then(ParseMatch("d")),
+            );
+
+        let answer = cache.parse(&p, "abd!");
+        assert_eq!(
+            answer,
+            Ok((("ab".to_string(), "d".to_string()), "!".to_string()))
+        );
+        assert_eq!(runs.get(), 1);
+
+        let stats = cache.stats();
+        assert_eq!((stats[0].hits, stats[0].misses), (1, 1));
+
+        // Parsing a new input must not reuse the old results
+        assert!(cache.parse(&p, "xxxx").is_err());
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[test]
+    fn different_inputs_without_clearing() {
+        let runs = Cell::new(0);
+        let cache = MemoCache::new();
+        let p = Counting(&runs).memoize(&cache, "ab");
+
+        assert!(p.parse("abc").is_ok());
+        // A suffix of the last input can reuse its results
+        assert!(p.parse("abc").is_ok());
+        assert_eq!(runs.get(), 1);
+
+        // Same length, different input
+        assert!(p.parse("xyz").is_err());
+        assert_eq!(p.parse("abz"), Ok(("ab".to_string(), "z".to_string())));
+        assert_eq!(runs.get(), 3);
+    }
+
+    #[test]
+    fn failures_are_cached() {
+        let runs = Cell::new(0);
+        let cache = MemoCache::new();
+        let p = Counting(&runs)
+            .memoize(&cache, "ab")
+            .otherwise(Counting(&runs).memoize(&cache, "ab"));
+
+        assert!(cache.parse(&p, "ba").is_err());
+        assert_eq!(runs.get(), 1);
+        assert_eq!(cache.stats()[0].hits, 1);
+    }
+}
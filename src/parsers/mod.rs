@@ -1,10 +1,31 @@
 pub mod and_p;
+pub mod class_p;
+pub mod either_p;
+pub mod ident_p;
+pub mod indent_p;
+pub mod iter_p;
+pub mod label_p;
+pub mod look_p;
 pub mod map_p;
+pub mod memo_p;
+pub mod opt_p;
 pub mod or_p;
+pub mod profile_p;
+#[cfg(feature = "regex")]
+pub mod regex_p;
 pub mod repeat_p;
+pub mod rule_p;
+pub mod state_p;
 pub mod string_p;
+pub mod take_p;
+pub mod trace_p;
 
-use crate::{errors::ParsingError, traits::Parser, type_alias::ParserRes};
+use crate::{
+    describe::{CharPredicate, Description},
+    errors::ParsingError,
+    traits::Parser,
+    type_alias::ParserRes,
+};
 
 /// A parser that will parse an exact input string
 ///
@@ -39,9 +60,13 @@ where
                 input, match_str
             )));
         }
-        let rest = input.chars().skip(match_str.len()).collect();
+        let rest = input[match_str.len()..].to_string();
         Ok((match_str, rest))
     }
+
+    fn describe(&self) -> Description {
+        Description::Literal(self.0.clone().into())
+    }
 }
 
 /// Parse a character if a predicate is met, otherwise, return an error.
@@ -58,19 +83,39 @@ where
 /// let answer = parse_if.parse("12hello");
 /// assert_eq!(answer, Ok(('1', "2hello".to_string())));
 /// ```
-pub struct ParseIf(pub fn(char) -> bool);
+///
+/// The predicate can be a closure that captures data, such as a `CharSet`
+///
+/// ```rust
+/// use mini_parc::parsers::{class_p::one_of, ParseIf};
+/// use mini_parc::traits::Parser;
+///
+/// let operators = one_of("+-*/");
+/// let parse_operator = ParseIf(move |c| operators.contains(c));
+/// assert_eq!(parse_operator.parse("*2"), Ok(('*', "2".to_string())));
+/// ```
+pub struct ParseIf<F = fn(char) -> bool>(pub F)
+where
+    F: Fn(char) -> bool;
 
-impl Parser for ParseIf {
+impl<F> Parser for ParseIf<F>
+where
+    F: Fn(char) -> bool,
+{
     type Output = char;
     fn parse(&self, input: &str) -> ParserRes<Self::Output> {
         let maybe_first_char = input.chars().next();
-        if let Some(true) = maybe_first_char.map(self.0) {
+        if let Some(true) = maybe_first_char.map(&self.0) {
             return Ok((maybe_first_char.unwrap(), input.chars().skip(1).collect()));
         }
         Err(ParsingError::PatternNotFound(
             "if predicate not met".to_string(),
         ))
     }
+
+    fn describe(&self) -> Description {
+        Description::Char(CharPredicate::sampled("character", &self.0))
+    }
 }
 
 /// Keep parsing characters while some predicate is met. If none of the characters
@@ -104,15 +149,30 @@ impl Parser for ParseIf {
 /// );
 /// ```
 #[derive(Debug, Clone)]
-pub struct ParseWhileOrNothing(pub fn(char) -> bool);
+pub struct ParseWhileOrNothing<F = fn(char) -> bool>(pub F)
+where
+    F: Fn(char) -> bool;
 
-impl Parser for ParseWhileOrNothing {
+impl<F> Parser for ParseWhileOrNothing<F>
+where
+    F: Fn(char) -> bool,
+{
     type Output = String;
     fn parse(&self, input: &str) -> ParserRes<Self::Output> {
         let taken = input.chars().take_while(|&x| self.0(x)).collect::<String>();
         let rest = input.chars().skip_while(|&x| self.0(x)).collect::<String>();
         Ok((taken, rest))
     }
+
+    fn describe(&self) -> Description {
+        Description::Repeat {
+            inner: Box::new(Description::Char(CharPredicate::sampled(
+                "character",
+                &self.0,
+            ))),
+            min: 0,
+        }
+    }
 }
 /// Keep parsing characters while some predicate is met. If none of the characters
 /// meet the predicate, and error will be returned. If this is not desired, try
@@ -147,9 +207,14 @@ impl Parser for ParseWhileOrNothing {
 /// );
 /// ```
 #[derive(Debug, Clone)]
-pub struct ParseWhile(pub fn(char) -> bool);
+pub struct ParseWhile<F = fn(char) -> bool>(pub F)
+where
+    F: Fn(char) -> bool;
 
-impl Parser for ParseWhile {
+impl<F> Parser for ParseWhile<F>
+where
+    F: Fn(char) -> bool,
+{
     type Output = String;
     fn parse(&self, input: &str) -> ParserRes<Self::Output> {
         let taken = input.chars().take_while(|&x| self.0(x)).collect::<String>();
@@ -161,6 +226,16 @@ impl Parser for ParseWhile {
         let rest = input.chars().skip_while(|&x| self.0(x)).collect::<String>();
         Ok((taken, rest))
     }
+
+    fn describe(&self) -> Description {
+        Description::Repeat {
+            inner: Box::new(Description::Char(CharPredicate::sampled(
+                "character",
+                &self.0,
+            ))),
+            min: 1,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -175,6 +250,14 @@ mod test_base_parsers {
         assert_eq!(answer, Ok(("if".to_string(), " and".to_string())));
     }
 
+    #[test]
+    fn match_parser_multibyte() {
+        // The rest of the input starts after the bytes of the match, not after as many chars
+        let parse_euro = ParseMatch("é€");
+        let answer = parse_euro.parse("é€ and");
+        assert_eq!(answer, Ok(("é€".to_string(), " and".to_string())));
+    }
+
     #[test]
     fn if_parser() {
         let parse_if = ParseIf(|c| c.is_numeric());
@@ -182,6 +265,16 @@ mod test_base_parsers {
         assert_eq!(answer, Ok(('1', "2hello".to_string())));
     }
 
+    #[test]
+    fn borrowing_predicate() {
+        let allowed = String::from("ab");
+        let p = ParseIf(|c| allowed.contains(c));
+        assert_eq!(p.parse("bc"), Ok(('b', "c".to_string())));
+        let p = ParseWhile(|c| allowed.contains(c));
+        assert_eq!(p.parse("abc"), Ok(("ab".to_string(), "c".to_string())));
+        assert_eq!(p.describe().to_string(), "start = <character>+ ;");
+    }
+
     #[test]
     fn parse_while() {
         let parse_numbers = ParseWhile(|c| c.is_numeric());
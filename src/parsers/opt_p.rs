@@ -0,0 +1,174 @@
+use std::fmt::Debug;
+
+use crate::{
+    describe::Description,
+    lexer::Token,
+    state::ParseState,
+    traits::{Parser, StreamParser},
+    type_alias::{ParserRes, TokenRes},
+};
+
+/// Try to run a parser, if it fails, `None` is returned and no input is consumed.
+///
+/// If it fails after an `expect` further into the input (see `ExpectParser`), its error is
+/// returned instead.
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{ParseMatch, ParseWhile};
+/// use mini_parc::traits::Parser;
+///
+/// let signed = ParseMatch("-").optional().and_then(ParseWhile(|c| c.is_numeric()));
+/// assert_eq!(
+///     signed.parse("-12"),
+///     Ok(((Some("-".to_string()), "12".to_string()), String::new()))
+/// );
+/// assert_eq!(
+///     signed.parse("12"),
+///     Ok(((None, "12".to_string()), String::new()))
+/// );
+/// ```
+pub struct OptionalParser<P>(pub P);
+
+impl<P> Parser for OptionalParser<P>
+where
+    P: Parser,
+{
+    type Output = Option<P::Output>;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let snapshot = state.snapshot();
+        match self.0.parse_stateful(input, state) {
+            Ok((out, rest)) => Ok((Some(out), rest)),
+            Err(e) if e.is_committed(input) => Err(e),
+            Err(_) => {
+                state.restore(snapshot);
+                Ok((None, input.to_string()))
+            }
+        }
+    }
+
+    fn describe(&self) -> Description {
+        self.0.describe().optional()
+    }
+}
+
+impl<K, P> StreamParser<K> for OptionalParser<P>
+where
+    P: StreamParser<K>,
+{
+    type Output = Option<P::Output>;
+    fn parse_tokens<'t>(&self, tokens: &'t [Token<K>]) -> TokenRes<'t, K, Self::Output> {
+        match self.0.parse_tokens(tokens) {
+            Ok((out, rest)) => Ok((Some(out), rest)),
+            Err(_) => Ok((None, tokens)),
+        }
+    }
+}
+
+/// Try to run a parser, if it fails, the given value is returned and no input is consumed.
+///
+/// Like `OptionalParser`, the error is returned if it fails past an `expect`.
+pub struct OrValueParser<P, T> {
+    pub parser: P,
+    pub value: T,
+}
+
+impl<P, T> Parser for OrValueParser<P, T>
+where
+    P: Parser<Output = T>,
+    T: Debug + Clone,
+{
+    type Output = T;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let snapshot = state.snapshot();
+        match self.parser.parse_stateful(input, state) {
+            Err(e) if !e.is_committed(input) => {
+                state.restore(snapshot);
+                Ok((self.value.clone(), input.to_string()))
+            }
+            res => res,
+        }
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe().optional()
+    }
+}
+
+/// Run a parser, and replace its output with a constant value
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::ParseMatch;
+/// use mini_parc::traits::Parser;
+///
+/// let boolean = ParseMatch("true").value(true).otherwise(ParseMatch("false").value(false));
+/// assert_eq!(boolean.parse("false"), Ok((false, String::new())));
+/// ```
+pub struct ValueParser<P, T> {
+    pub parser: P,
+    pub value: T,
+}
+
+impl<P, T> Parser for ValueParser<P, T>
+where
+    P: Parser,
+    T: Debug + Clone,
+{
+    type Output = T;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (_, rest) = self.parser.parse_stateful(input, state)?;
+        Ok((self.value.clone(), rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+#[cfg(test)]
+mod optional_parser_test {
+    use crate::parsers::and_p::KeepFirstOutputOnly;
+    use crate::parsers::{ParseMatch, ParseWhile};
+    use crate::traits::Parser;
+
+    #[test]
+    fn optional_trailing_comma() {
+        let list = ParseWhile(|c| c.is_alphabetic())
+            .and_then(ParseMatch(",").optional())
+            .combine(KeepFirstOutputOnly)
+            .and_then(ParseMatch("]"));
+
+        assert!(list.parse("a,]").is_ok());
+        assert!(list.parse("a]").is_ok());
+        assert!(list.parse("a;]").is_err());
+    }
+
+    #[test]
+    fn default_values() {
+        let sign = ParseMatch("-")
+            .value(-1)
+            .or_value(1)
+            .and_then(ParseWhile(|c| c.is_numeric()).with_try_mapping(&|s| s.parse::<i32>().ok()))
+            .with_mapping(&|(sign, n)| sign * n);
+        assert_eq!(sign.parse("-5"), Ok((-5, String::new())));
+        assert_eq!(sign.parse("5"), Ok((5, String::new())));
+
+        let digits = ParseWhile(|c| c.is_numeric()).or_default();
+        assert_eq!(digits.parse("x"), Ok((String::new(), "x".to_string())));
+    }
+}
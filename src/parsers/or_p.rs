@@ -1,21 +1,26 @@
 use std::fmt::Debug;
 
-use crate::{errors::ParsingError, traits::Parser, type_alias::ParserRes};
+use crate::{
+    describe::Description,
+    errors::ParsingError,
+    lexer::Token,
+    state::ParseState,
+    traits::{Parser, StreamParser},
+    type_alias::{ParserRes, TokenRes},
+};
 
-pub struct OrThenParser<A, B>
-where
-    A: Parser,
-    B: Parser,
-{
+/// Try a parser, and if it fails, try another one on the same input.
+///
+/// If the first parser fails after an `expect` further into the input (see `ExpectParser`),
+/// the other one is not tried, and its error is returned.
+///
+/// It is also a parser of tokens (see `StreamParser`), when A and B are.
+pub struct OrThenParser<A, B> {
     pub first_parse: A,
     pub second_parse: B,
 }
 
-impl<A, B> From<(A, B)> for OrThenParser<A, B>
-where
-    A: Parser,
-    B: Parser,
-{
+impl<A, B> From<(A, B)> for OrThenParser<A, B> {
     fn from((ap, bp): (A, B)) -> Self {
         Self {
             first_parse: ap,
@@ -32,18 +37,48 @@ where
 {
     type Output = CommonOut;
     fn parse(&self, input: &str) -> ParserRes<Self::Output> {
-        let aparse = self.first_parse.parse(input);
-        if aparse.is_ok() {
-            return aparse;
-        }
+        self.parse_stateful(input, &mut ())
+    }
+
+    /// If a branch fails, the state is restored to what it was before that branch was tried
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let snapshot = state.snapshot();
+        let aerr = match self.first_parse.parse_stateful(input, state) {
+            Err(e) if !e.is_committed(input) => e,
+            aparse => return aparse,
+        };
+        state.restore(snapshot);
 
-        let bparse = self.second_parse.parse(input);
-        if bparse.is_ok() {
-            return bparse;
+        let snapshot = state.snapshot();
+        let berr = match self.second_parse.parse_stateful(input, state) {
+            Err(e) if !e.is_committed(input) => e,
+            bparse => return bparse,
         };
+        state.restore(snapshot);
 
-        Err(ParsingError::PatternNotFound(
-            "Or Parser didnt match either of the branches".to_string(),
+        Err(ParsingError::of_choice(
+            [aerr, berr],
+            ParsingError::PatternNotFound(
+                "Or Parser didnt match either of the branches".to_string(),
+            ),
         ))
     }
+
+    fn describe(&self) -> Description {
+        self.first_parse.describe().or(self.second_parse.describe())
+    }
+}
+
+impl<K, A, B, CommonOut> StreamParser<K> for OrThenParser<A, B>
+where
+    A: StreamParser<K, Output = CommonOut>,
+    B: StreamParser<K, Output = CommonOut>,
+    CommonOut: Debug,
+{
+    type Output = CommonOut;
+    fn parse_tokens<'t>(&self, tokens: &'t [Token<K>]) -> TokenRes<'t, K, Self::Output> {
+        self.first_parse
+            .parse_tokens(tokens)
+            .or_else(|_| self.second_parse.parse_tokens(tokens))
+    }
 }
@@ -0,0 +1,294 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use super::memo_p::RuleRegistry;
+use crate::{describe::Description, state::ParseState, traits::Parser, type_alias::ParserRes};
+
+/// What a `Profiler` measured for one rule.
+///
+/// Times include the time spent in any rules nested inside this one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleProfile {
+    pub rule: String,
+    pub calls: usize,
+    pub successes: usize,
+    pub failures: usize,
+    /// Bytes consumed by the successful calls
+    pub bytes_consumed: usize,
+    pub time: Duration,
+    /// Time spent in calls that failed, which was wasted by backtracking
+    pub failed_time: Duration,
+    /// Calls at a position where the rule had already been run, for the same input
+    pub reparses: usize,
+}
+
+/// Collects statistics of the parsers made with `Parser::profile`, to find the rules where
+/// parsing time is spent, and which ones backtrack the most.
+///
+/// Parsers profiled with the same name are counted as the same rule. Re-parses are found by
+/// position, so `Profiler::parse` (or `Profiler::new_input`) should be used when parsing a
+/// different input.
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{profile_p::Profiler, ParseMatch, ParseWhile};
+/// use mini_parc::traits::Parser;
+///
+/// let profiler = Profiler::new();
+/// let number = || ParseWhile(|c| c.is_numeric()).profile(&profiler, "number");
+/// let p = number()
+///     .and_then(ParseMatch("%"))
+///     .otherwise(number().and_then(ParseMatch("px")));
+///
+/// assert!(profiler.parse(&p, "12px").is_ok());
+/// let number = &profiler.report().rules[0];
+/// assert_eq!((number.calls, number.successes, number.reparses), (2, 2, 1));
+/// ```
+#[derive(Default)]
+pub struct Profiler {
+    rules: RefCell<RuleRegistry<RuleProfile>>,
+    /// (rule id, position) of every call since the last new input
+    seen: RefCell<HashSet<(usize, usize)>>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the positions that rules were run at, but keep the statistics
+    pub fn new_input(&self) {
+        self.seen.borrow_mut().clear();
+    }
+
+    /// Call `new_input`, then run the parser on the input
+    pub fn parse<P>(&self, parser: &P, input: &str) -> ParserRes<P::Output>
+    where
+        P: Parser,
+    {
+        self.new_input();
+        parser.parse(input)
+    }
+
+    /// Set all of the statistics back to zero
+    pub fn reset(&self) {
+        self.new_input();
+        for p in self.rules.borrow_mut().entries_mut() {
+            *p = RuleProfile {
+                rule: std::mem::take(&mut p.rule),
+                ..Default::default()
+            };
+        }
+    }
+
+    /// The statistics of every rule, hottest (most time) first
+    pub fn report(&self) -> ProfileReport {
+        let mut rules = self.rules.borrow().entries().to_vec();
+        rules.sort_by(|a, b| b.time.cmp(&a.time).then(b.calls.cmp(&a.calls)));
+        ProfileReport { rules }
+    }
+}
+
+/// The statistics of a `Profiler`, see `Profiler::report`.
+///
+/// Displaying a report gives a table of the rules, followed by a summary of the work wasted
+/// by backtracking.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileReport {
+    pub rules: Vec<RuleProfile>,
+}
+
+impl ProfileReport {
+    pub fn rule(&self, name: &str) -> Option<&RuleProfile> {
+        self.rules.iter().find(|r| r.rule == name)
+    }
+
+    /// Rules sorted by how much time their failed calls took, ignoring those that never
+    /// failed or re-parsed
+    pub fn backtracking(&self) -> Vec<&RuleProfile> {
+        let mut rules: Vec<_> = self
+            .rules
+            .iter()
+            .filter(|r| r.failures > 0 || r.reparses > 0)
+            .collect();
+        rules.sort_by(|a, b| {
+            b.failed_time
+                .cmp(&a.failed_time)
+                .then(b.reparses.cmp(&a.reparses))
+        });
+        rules
+    }
+}
+
+impl Display for ProfileReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .rules
+            .iter()
+            .map(|r| r.rule.len())
+            .chain(Some(4))
+            .max()
+            .unwrap_or_default();
+        writeln!(
+            f,
+            "{:width$} {:>8} {:>8} {:>8} {:>10} {:>12} {:>12} {:>9}",
+            "rule", "calls", "ok", "failed", "bytes", "time", "failed time", "reparses"
+        )?;
+        for r in &self.rules {
+            writeln!(
+                f,
+                "{:width$} {:>8} {:>8} {:>8} {:>10} {:>12} {:>12} {:>9}",
+                r.rule,
+                r.calls,
+                r.successes,
+                r.failures,
+                r.bytes_consumed,
+                format!("{:.2?}", r.time),
+                format!("{:.2?}", r.failed_time),
+                r.reparses
+            )?;
+        }
+
+        let failed: Duration = self.rules.iter().map(|r| r.failed_time).sum();
+        let reparses: usize = self.rules.iter().map(|r| r.reparses).sum();
+        writeln!(
+            f,
+            "backtracking: {failed:.2?} in failed calls, {reparses} reparses"
+        )
+    }
+}
+
+/// A parser whose calls are measured by a `Profiler`
+pub struct ProfileParser<'p, P> {
+    pub parser: P,
+    rule: usize,
+    profiler: &'p Profiler,
+}
+
+impl<'p, P> ProfileParser<'p, P> {
+    pub fn new<S>(parser: P, profiler: &'p Profiler, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        let rule = profiler
+            .rules
+            .borrow_mut()
+            .id(name.into(), |name| RuleProfile {
+                rule: name.to_string(),
+                ..Default::default()
+            });
+        Self {
+            parser,
+            rule,
+            profiler,
+        }
+    }
+}
+
+impl<'p, P> Parser for ProfileParser<'p, P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let reparse = !self
+            .profiler
+            .seen
+            .borrow_mut()
+            .insert((self.rule, input.len()));
+
+        let start = Instant::now();
+        let res = self.parser.parse_stateful(input, state);
+        let elapsed = start.elapsed();
+
+        let mut profiles = self.profiler.rules.borrow_mut();
+        let profile = &mut profiles[self.rule];
+        profile.calls += 1;
+        profile.time += elapsed;
+        profile.reparses += usize::from(reparse);
+        match &res {
+            Ok((_, rest)) => {
+                profile.successes += 1;
+                profile.bytes_consumed += input.len() - rest.len();
+            }
+            Err(_) => {
+                profile.failures += 1;
+                profile.failed_time += elapsed;
+            }
+        }
+        res
+    }
+
+    fn describe(&self) -> Description {
+        let name = self.profiler.rules.borrow()[self.rule].rule.clone();
+        Description::named(name, self.parser.describe())
+    }
+}
+
+#[cfg(test)]
+mod profile_parser_test {
+    use super::Profiler;
+    use crate::parsers::{ParseMatch, ParseWhile};
+    use crate::traits::Parser;
+
+    #[test]
+    fn counts_and_backtracking() {
+        let profiler = Profiler::new();
+        let word = || ParseWhile(|c| c.is_alphabetic()).profile(&profiler, "word");
+        let keyword = ParseMatch("let").profile(&profiler, "keyword");
+        let statement = keyword
+            .and_then(ParseMatch(" "))
+            .and_then(word())
+            .value(())
+            .otherwise(word().value(()))
+            .profile(&profiler, "statement");
+
+        assert!(profiler.parse(&statement, "let x").is_ok());
+        assert!(profiler.parse(&statement, "go").is_ok());
+        assert!(profiler.parse(&statement, "1").is_err());
+
+        let report = profiler.report();
+        let word = report.rule("word").unwrap();
+        assert_eq!((word.calls, word.successes, word.failures), (3, 2, 1));
+        assert_eq!(word.bytes_consumed, 3);
+        let keyword = report.rule("keyword").unwrap();
+        assert_eq!((keyword.calls, keyword.failures), (3, 2));
+        assert_eq!(report.rule("statement").unwrap().bytes_consumed, 7);
+
+        let backtracking: Vec<&str> = report
+            .backtracking()
+            .iter()
+            .map(|r| r.rule.as_str())
+            .collect();
+        assert_eq!(backtracking.len(), 3);
+        assert!(report.to_string().starts_with("rule"));
+
+        profiler.reset();
+        assert_eq!(profiler.report().rule("word").unwrap().calls, 0);
+    }
+
+    #[test]
+    fn reparses() {
+        let profiler = Profiler::new();
+        let digits = || ParseWhile(|c| c.is_numeric()).profile(&profiler, "digits");
+        let p = digits()
+            .and_then(ParseMatch("."))
+            .value(())
+            .otherwise(digits().and_then(ParseMatch("e")).value(()))
+            .otherwise(digits().value(()));
+
+        assert!(profiler.parse(&p, "12").is_ok());
+        assert!(profiler.parse(&p, "12").is_ok());
+        let digits = profiler.report().rules[0].clone();
+        assert_eq!((digits.calls, digits.reparses), (6, 4));
+    }
+}
@@ -0,0 +1,185 @@
+//! Parsers made from regular expressions, enabled with the `regex` feature.
+//!
+//! The expressions are anchored at the start of the input, so they only match what comes
+//! next, like every other parser. `ParseRegex` outputs the text that was matched, and
+//! `ParseCaptures` also outputs the capture groups.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::parsers::regex_p::{ParseCaptures, ParseRegex};
+//! use mini_parc::traits::Parser;
+//!
+//! let ident = ParseRegex::new(r"[\p{XID_Start}_]\p{XID_Continue}*").unwrap();
+//! assert_eq!(ident.parse("état = 1"), Ok(("état".to_string(), " = 1".to_string())));
+//! assert!(ident.parse(" x").is_err());
+//!
+//! let date = ParseCaptures::new(r"(?<year>\d{4})-(?<month>\d{2})-(\d{2})").unwrap();
+//! let (captures, rest) = date.parse("2024-02-29T12:00").unwrap();
+//! assert_eq!(captures.name("month"), Some("02"));
+//! assert_eq!(captures.get(3), Some("29"));
+//! assert_eq!(rest, "T12:00");
+//! ```
+
+use regex::Regex;
+
+use crate::{describe::Description, errors::ParsingError, traits::Parser, type_alias::ParserRes};
+
+/// Compile a pattern so that it only matches at the start of the input
+fn anchored(pattern: &str) -> Result<Regex, ParsingError> {
+    let invalid = |e: regex::Error| {
+        ParsingError::PatternNotFound(format!("invalid regular expression /{pattern}/: {e}"))
+    };
+    // The pattern is checked on its own first, otherwise an unbalanced one such as `a)|(b`
+    // could close the group that anchors it
+    Regex::new(pattern).map_err(invalid)?;
+    Regex::new(&format!(r"\A(?:{pattern})")).map_err(invalid)
+}
+
+fn no_match(pattern: &str) -> ParsingError {
+    ParsingError::PatternNotFound(format!("input did not match /{pattern}/"))
+}
+
+/// Parse the text matched by a regular expression
+pub struct ParseRegex {
+    regex: Regex,
+    pattern: String,
+}
+
+impl ParseRegex {
+    /// Compile the regular expression, this fails if it is not valid
+    pub fn new(pattern: &str) -> Result<Self, ParsingError> {
+        Ok(Self {
+            regex: anchored(pattern)?,
+            pattern: pattern.to_string(),
+        })
+    }
+}
+
+impl Parser for ParseRegex {
+    type Output = String;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        match self.regex.find(input) {
+            Some(m) if m.start() == 0 => Ok((m.as_str().to_string(), input[m.end()..].to_string())),
+            _ => Err(no_match(&self.pattern)),
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::Predicate(format!("/{}/", self.pattern))
+    }
+}
+
+/// The capture groups of a match, see `ParseCaptures`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegexCaptures {
+    /// The text of every group, the first one is the whole match. Groups that did not take
+    /// part in the match are `None`.
+    pub groups: Vec<Option<String>>,
+    /// The name of every group, if it has one
+    pub names: Vec<Option<String>>,
+}
+
+impl RegexCaptures {
+    /// The text of a group, 0 is the whole match
+    pub fn get(&self, group: usize) -> Option<&str> {
+        self.groups.get(group)?.as_deref()
+    }
+
+    /// The text of a named group
+    pub fn name(&self, name: &str) -> Option<&str> {
+        let group = self.names.iter().position(|n| n.as_deref() == Some(name))?;
+        self.get(group)
+    }
+}
+
+/// Parse the text matched by a regular expression, and output its capture groups
+pub struct ParseCaptures {
+    regex: Regex,
+    pattern: String,
+}
+
+impl ParseCaptures {
+    /// Compile the regular expression, this fails if it is not valid
+    pub fn new(pattern: &str) -> Result<Self, ParsingError> {
+        Ok(Self {
+            regex: anchored(pattern)?,
+            pattern: pattern.to_string(),
+        })
+    }
+}
+
+impl Parser for ParseCaptures {
+    type Output = RegexCaptures;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        let Some(captures) = self.regex.captures(input) else {
+            return Err(no_match(&self.pattern));
+        };
+        let Some(whole) = captures.get(0).filter(|m| m.start() == 0) else {
+            return Err(no_match(&self.pattern));
+        };
+        let groups = captures
+            .iter()
+            .map(|m| m.map(|m| m.as_str().to_string()))
+            .collect();
+        let names = self
+            .regex
+            .capture_names()
+            .map(|n| n.map(str::to_string))
+            .collect();
+        Ok((
+            RegexCaptures { groups, names },
+            input[whole.end()..].to_string(),
+        ))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Predicate(format!("/{}/", self.pattern))
+    }
+}
+
+#[cfg(test)]
+mod regex_parser_test {
+    use super::{ParseCaptures, ParseRegex};
+    use crate::errors::ParsingError;
+    use crate::parsers::ParseMatch;
+    use crate::traits::Parser;
+
+    #[test]
+    fn anchored_at_the_start() {
+        let uuid = ParseRegex::new("[0-9a-f]{8}(-[0-9a-f]{4}){3}-[0-9a-f]{12}").unwrap();
+        let input = "id 123e4567-e89b-12d3-a456-426614174000";
+        assert_eq!(
+            uuid.parse(input),
+            Err(ParsingError::PatternNotFound(
+                "input did not match /[0-9a-f]{8}(-[0-9a-f]{4}){3}-[0-9a-f]{12}/".to_string()
+            ))
+        );
+
+        let p = ParseMatch("id ").and_then(uuid);
+        assert_eq!(p.parse_complete(input).map(|(_, id)| id.len()), Ok(36));
+
+        // An alternation is anchored as a whole, and `^` only matches at the very start
+        let word = ParseRegex::new("a|b").unwrap();
+        assert!(word.parse("ca").is_err());
+        assert!(ParseRegex::new("(?m)^x").unwrap().parse("\nx").is_err());
+    }
+
+    #[test]
+    fn captures_and_errors() {
+        let timestamp = ParseCaptures::new(r"(\d{2}):(\d{2})(?::(?<seconds>\d{2}))?").unwrap();
+        let (captures, rest) = timestamp.parse("12:30 pm").unwrap();
+        assert_eq!(captures.get(0), Some("12:30"));
+        assert_eq!((captures.get(1), captures.get(2)), (Some("12"), Some("30")));
+        assert_eq!(captures.name("seconds"), None);
+        assert_eq!(rest, " pm");
+
+        let (captures, _) = timestamp.parse("12:30:15").unwrap();
+        assert_eq!(captures.name("seconds"), Some("15"));
+
+        assert!(ParseRegex::new("(").is_err());
+        // Unbalanced patterns can not escape the anchor
+        assert!(ParseRegex::new("a)|(b").is_err());
+        assert!(ParseCaptures::new("a)|(b").is_err());
+    }
+}
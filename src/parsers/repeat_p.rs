@@ -1,5 +1,81 @@
-use crate::{errors::ParsingError, traits::Parser};
+use std::{fmt::Debug, marker::PhantomData};
 
+use crate::{
+    describe::Description,
+    errors::ParsingError,
+    lexer::Token,
+    state::ParseState,
+    traits::{Parser, StreamParser},
+    type_alias::{ParserRes, TokenRes},
+};
+
+/// Runs a parser over and over on the rest of the input, yielding its outputs.
+///
+/// It stops at the first failure, or as soon as the parser succeeds without consuming any
+/// input (otherwise it would loop forever). In both cases, the state is restored to what it
+/// was before that last attempt.
+///
+/// If the parser fails after an `expect` further into the input than where it started (see
+/// `ExpectParser`), the error is kept, and the parsers using it fail with it.
+struct Repetitions<'a, P> {
+    parser: &'a P,
+    state: &'a mut dyn ParseState,
+    rest: String,
+    done: bool,
+    error: Option<ParsingError>,
+}
+
+impl<'a, P> Repetitions<'a, P> {
+    fn new(parser: &'a P, input: &str, state: &'a mut dyn ParseState) -> Self {
+        Self {
+            parser,
+            state,
+            rest: input.to_string(),
+            done: false,
+            error: None,
+        }
+    }
+
+    /// The rest of the input after the repetitions, or the error they were stopped by
+    fn finish(self) -> Result<String, ParsingError> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.rest),
+        }
+    }
+}
+
+impl<'a, P> Iterator for Repetitions<'a, P>
+where
+    P: Parser,
+{
+    type Item = P::Output;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let snapshot = self.state.snapshot();
+        match self.parser.parse_stateful(&self.rest, self.state) {
+            Ok((p, r)) if r.len() < self.rest.len() => {
+                self.rest = r;
+                Some(p)
+            }
+            Err(e) if e.is_committed(&self.rest) => {
+                self.error = Some(e);
+                self.done = true;
+                None
+            }
+            _ => {
+                self.state.restore(snapshot);
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+/// Parse the same thing one or more times, collecting the outputs into a `Vec`
 pub struct RepeatParser<P>(P);
 
 impl<P> RepeatParser<P> {
@@ -13,16 +89,47 @@ where
     P: Parser,
 {
     type Output = Vec<P::Output>;
-    fn parse(&self, input: &str) -> crate::type_alias::ParserRes<Self::Output> {
-        let mut rest = input.to_string();
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let mut reps = Repetitions::new(&self.0, input, state);
+        let acc = reps.by_ref().collect::<Vec<_>>();
+        let rest = reps.finish()?;
+
+        if acc.is_empty() {
+            return Err(ParsingError::PatternNotFound(
+                "Did not match parser any times".to_string(),
+            ));
+        }
+
+        Ok((acc, rest))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Repeat {
+            inner: Box::new(self.0.describe()),
+            min: 1,
+        }
+    }
+}
+
+impl<K, P> StreamParser<K> for RepeatParser<P>
+where
+    P: StreamParser<K>,
+{
+    type Output = Vec<P::Output>;
+    fn parse_tokens<'t>(&self, tokens: &'t [Token<K>]) -> TokenRes<'t, K, Self::Output> {
         let mut acc = vec![];
-        loop {
-            let Ok((p, r)) = self.0.parse(&rest) else {
+        let mut rest = tokens;
+        // Stop when the parser fails, or when it does not consume any tokens
+        while let Ok((out, r)) = self.0.parse_tokens(rest) {
+            if r.len() >= rest.len() {
                 break;
-            };
-
+            }
+            acc.push(out);
             rest = r;
-            acc.push(p);
         }
 
         if acc.is_empty() {
@@ -30,16 +137,195 @@ where
                 "Did not match parser any times".to_string(),
             ));
         }
+        Ok((acc, rest))
+    }
+}
 
-        Ok((acc, String::new()))
+/// Parse the same thing one or more times, collecting the outputs into any collection
+/// (see `Parser::collect_into`)
+pub struct CollectParser<P, C> {
+    pub parser: P,
+    collection: PhantomData<C>,
+}
+
+impl<P, C> CollectParser<P, C> {
+    pub fn new(parser: P) -> Self {
+        Self {
+            parser,
+            collection: PhantomData,
+        }
     }
 }
 
+impl<P, C> Parser for CollectParser<P, C>
+where
+    P: Parser,
+    C: FromIterator<P::Output> + Debug,
+{
+    type Output = C;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let mut reps = Repetitions::new(&self.parser, input, state);
+        let mut matched = false;
+        let collection = reps.by_ref().inspect(|_| matched = true).collect();
+        let rest = reps.finish()?;
+
+        if !matched {
+            return Err(ParsingError::PatternNotFound(
+                "Did not match parser any times".to_string(),
+            ));
+        }
+
+        Ok((collection, rest))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Repeat {
+            inner: Box::new(self.parser.describe()),
+            min: 1,
+        }
+    }
+}
+
+/// Parse the same thing zero or more times, combining the outputs as they are parsed
+pub struct FoldParser<'a, P: Parser, Acc> {
+    pub parser: P,
+    pub init: Acc,
+    pub fold: &'a dyn Fn(Acc, P::Output) -> Acc,
+}
+
+impl<'a, P, Acc> Parser for FoldParser<'a, P, Acc>
+where
+    P: Parser,
+    Acc: Debug + Clone,
+{
+    type Output = Acc;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let mut reps = Repetitions::new(&self.parser, input, state);
+        let acc = reps.by_ref().fold(self.init.clone(), self.fold);
+        Ok((acc, reps.finish()?))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Repeat {
+            inner: Box::new(self.parser.describe()),
+            min: 0,
+        }
+    }
+}
+
+/// Parse the same thing zero or more times, and output how many times it was parsed
+pub struct CountParser<P>(pub P);
+
+impl<P> Parser for CountParser<P>
+where
+    P: Parser,
+{
+    type Output = usize;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let mut reps = Repetitions::new(&self.0, input, state);
+        let count = reps.by_ref().count();
+        Ok((count, reps.finish()?))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Repeat {
+            inner: Box::new(self.0.describe()),
+            min: 0,
+        }
+    }
+}
+
+/// Parse the same thing zero or more times, and discard the outputs
+pub struct SkipParser<P>(pub P);
+
+impl<P> Parser for SkipParser<P>
+where
+    P: Parser,
+{
+    type Output = ();
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let mut reps = Repetitions::new(&self.0, input, state);
+        reps.by_ref().for_each(drop);
+        Ok(((), reps.finish()?))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Repeat {
+            inner: Box::new(self.0.describe()),
+            min: 0,
+        }
+    }
+}
+
+/// Parse the same thing zero or more times, combining the outputs with `fold`, starting
+/// from `init`. No `Vec` is allocated for the outputs.
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{
+///     and_p::KeepFirstOutputOnly, repeat_p::fold_many, ParseWhile, ParseWhileOrNothing,
+/// };
+/// use mini_parc::traits::Parser;
+///
+/// let number = ParseWhile(|c| c.is_numeric())
+///     .and_then(ParseWhileOrNothing(|c| c == ' '))
+///     .combine(KeepFirstOutputOnly)
+///     .with_try_mapping(&|s| s.parse::<u32>().ok());
+/// let sum = fold_many(number, 0, &|acc, n| acc + n);
+///
+/// assert_eq!(sum.parse("1 2 3;"), Ok((6, ";".to_string())));
+/// ```
+pub fn fold_many<'a, P, Acc>(
+    parser: P,
+    init: Acc,
+    fold: &'a dyn Fn(Acc, P::Output) -> Acc,
+) -> FoldParser<'a, P, Acc>
+where
+    P: Parser,
+{
+    FoldParser { parser, init, fold }
+}
+
+/// Parse the same thing zero or more times, and output how many times it was parsed
+pub fn count_many<P>(parser: P) -> CountParser<P>
+where
+    P: Parser,
+{
+    CountParser(parser)
+}
+
+/// Parse the same thing zero or more times, and discard the outputs
+pub fn skip_many<P>(parser: P) -> SkipParser<P>
+where
+    P: Parser,
+{
+    SkipParser(parser)
+}
+
 #[cfg(test)]
 mod parse_many_t {
+    use std::collections::BTreeSet;
 
+    use super::{count_many, fold_many, skip_many};
     use crate::parsers::and_p::KeepSecondOutputOnly;
-    use crate::parsers::{ParseWhile, ParseWhileOrNothing};
+    use crate::parsers::{ParseIf, ParseMatch, ParseWhile, ParseWhileOrNothing};
     use crate::traits::Parser;
 
     #[test]
@@ -60,4 +346,58 @@ mod parse_many_t {
 
         assert!(many_string_p.parse("").is_err())
     }
+
+    #[test]
+    fn remainder_and_zero_progress() {
+        let digits = super::RepeatParser(ParseIf(|c| c.is_numeric()));
+        assert_eq!(digits.parse("12ab"), Ok((vec!['1', '2'], "ab".to_string())));
+
+        // Matching nothing forever must not loop
+        let nothing = super::RepeatParser(ParseWhileOrNothing(|c| c.is_numeric()));
+        assert!(nothing.parse("ab").is_err());
+        assert_eq!(
+            skip_many(ParseWhileOrNothing(|c| c.is_numeric())).parse("1a"),
+            Ok(((), "a".to_string()))
+        );
+    }
+
+    #[test]
+    fn accumulate_without_vec() {
+        let line = ParseWhileOrNothing(|c| c != '\n').and_then(ParseMatch("\n"));
+        assert_eq!(
+            count_many(line).parse("a\nb\n\nc"),
+            Ok((3, "c".to_string()))
+        );
+
+        let digit = ParseIf(|c| c.is_numeric()).with_try_mapping(&|c| c.to_digit(10));
+        let sum = fold_many(digit, 0, &|acc, d| acc + d);
+        assert_eq!(sum.parse("123x"), Ok((6, "x".to_string())));
+        assert_eq!(sum.parse("x"), Ok((0, "x".to_string())));
+
+        let unique = ParseIf(|c| c.is_alphabetic()).collect_into::<BTreeSet<_>>();
+        assert_eq!(
+            unique.parse("abba!"),
+            Ok((BTreeSet::from(['a', 'b']), "!".to_string()))
+        );
+        let word = ParseIf(|c| c.is_alphabetic()).collect_into::<String>();
+        assert_eq!(word.parse("ab1"), Ok(("ab".to_string(), "1".to_string())));
+        assert!(word.parse("1").is_err());
+    }
+
+    #[test]
+    fn stop_at_expect() {
+        let digit = || ParseIf(|c| c.is_numeric());
+        let pair = digit().and_then(ParseMatch(",").and_then(digit()).expect("',' and a digit"));
+
+        assert_eq!(count_many(pair).parse("1,2x"), Ok((1, "x".to_string())));
+        // The second repetition matched a digit, but no ',' after it
+        let pair = digit().and_then(ParseMatch(",").and_then(digit()).expect("',' and a digit"));
+        assert_eq!(
+            count_many(pair)
+                .parse_complete("1,23x")
+                .unwrap_err()
+                .to_string(),
+            "expected ',' and a digit at line 1, column 5"
+        );
+    }
 }
@@ -0,0 +1,431 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt::Debug,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    describe::Description, errors::ParsingError, state::ParseState, traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// An object safe version of the `Parser` trait, this is implemented for every parser, and
+/// allows parsers to be stored behind a pointer.
+pub trait DynParser<O> {
+    fn dyn_parse(&self, input: &str) -> ParserRes<O>;
+    fn dyn_parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<O>;
+    fn dyn_describe(&self) -> Description;
+}
+
+impl<P> DynParser<P::Output> for P
+where
+    P: Parser,
+{
+    fn dyn_parse(&self, input: &str) -> ParserRes<P::Output> {
+        self.parse(input)
+    }
+
+    fn dyn_parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<P::Output> {
+        self.parse_stateful(input, state)
+    }
+
+    fn dyn_describe(&self) -> Description {
+        self.describe()
+    }
+}
+
+/// A parser stored behind a `Box`, this makes it possible to store parsers of different
+/// types (but the same output) together.
+pub struct BoxedParser<'a, O>(pub Box<dyn DynParser<O> + 'a>);
+
+impl<'a, O> Parser for BoxedParser<'a, O>
+where
+    O: Debug,
+{
+    type Output = O;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.0.dyn_parse(input)
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        self.0.dyn_parse_stateful(input, state)
+    }
+
+    fn describe(&self) -> Description {
+        self.0.dyn_describe()
+    }
+}
+
+/// Results of a left recursive rule that are currently being grown, keyed by position
+type Seeds<O> = HashMap<usize, Result<(O, usize), ParsingError>>;
+
+/// A grammar rule that can be referenced before it is defined, which allows rules to be
+/// recursive.
+///
+/// Cloning a rule gives a new handle to the same rule. Note that a rule that references
+/// itself makes a reference cycle, so rules should be built once and then reused, or refer
+/// to themselves through a weak handle (see `Rule::downgrade`).
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{rule_p::Rule, ParseMatch};
+/// use mini_parc::traits::Parser;
+///
+/// // nested = "(" nested ")" | "x"
+/// let nested = Rule::new();
+/// nested.define(
+///     ParseMatch("(")
+///         .and_then(nested.clone())
+///         .and_then(ParseMatch(")"))
+///         .with_mapping(&|((_, inner), _)| inner)
+///         .otherwise(ParseMatch("x")),
+/// );
+///
+/// assert_eq!(nested.parse("((x))"), Ok(("x".to_string(), String::new())));
+/// ```
+pub struct Rule<'a, O> {
+    body: Rc<RefCell<Option<BoxedParser<'a, O>>>>,
+    seeds: Option<Rc<RefCell<Seeds<O>>>>,
+    name: Option<String>,
+    /// Set while the rule is being described, so that recursive rules are only described once
+    describing: Rc<Cell<bool>>,
+}
+
+impl<'a, O> Clone for Rule<'a, O> {
+    fn clone(&self) -> Self {
+        Self {
+            body: self.body.clone(),
+            seeds: self.seeds.clone(),
+            name: self.name.clone(),
+            describing: self.describing.clone(),
+        }
+    }
+}
+
+impl<'a, O> Default for Rule<'a, O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, O> Rule<'a, O> {
+    /// Make a new, undefined, rule
+    pub fn new() -> Self {
+        Self {
+            body: Rc::new(RefCell::new(None)),
+            seeds: None,
+            name: None,
+            describing: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// Make a new, undefined, rule that may be left recursive, such as
+    /// `expr = expr "+" term | term`.
+    ///
+    /// The rule is parsed by growing a seed (see Warth et al, "Packrat Parsers Can Support
+    /// Left Recursion"). The first time the rule is reached at some position, calls to itself
+    /// at that same position will fail, the result of that first attempt is then used as the
+    /// answer of the recursive calls, and the rule is re-run for as long as it keeps consuming
+    /// more of the input.
+    ///
+    /// For indirect left recursion, at least one of the rules in the cycle must be made
+    /// with this function.
+    pub fn left_recursive() -> Self {
+        Self {
+            body: Rc::new(RefCell::new(None)),
+            seeds: Some(Rc::new(RefCell::new(HashMap::new()))),
+            name: None,
+            describing: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// Give the rule a name, which is used when it is described (see `Parser::describe`).
+    /// Only the handles made from this one, by cloning it, will have the name.
+    pub fn named<S>(mut self, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Make a handle to this rule that does not keep it alive. Rules that refer to each
+    /// other through weak handles do not make a reference cycle, so they are freed when the
+    /// last `Rule` handle is dropped.
+    pub fn downgrade(&self) -> WeakRule<'a, O> {
+        WeakRule {
+            body: Rc::downgrade(&self.body),
+            seeds: self.seeds.as_ref().map(Rc::downgrade),
+            name: self.name.clone(),
+            describing: Rc::downgrade(&self.describing),
+        }
+    }
+
+    /// Set the parser that this rule will run, replacing any previous definition
+    pub fn define<P>(&self, parser: P)
+    where
+        P: Parser<Output = O> + 'a,
+    {
+        *self.body.borrow_mut() = Some(BoxedParser(Box::new(parser)));
+    }
+
+    fn parse_body(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<O>
+    where
+        O: Debug,
+    {
+        match self.body.borrow().as_ref() {
+            Some(body) => body.parse_stateful(input, state),
+            None => Err(ParsingError::PatternNotFound(
+                "rule was used before being defined".to_string(),
+            )),
+        }
+    }
+}
+
+impl<'a, O> Parser for Rule<'a, O>
+where
+    O: Debug + Clone,
+{
+    type Output = O;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let Some(seeds) = &self.seeds else {
+            return self.parse_body(input, state);
+        };
+
+        let key = input.len();
+        let seed = seeds.borrow().get(&key).cloned();
+        if let Some(seed) = seed {
+            let rest = |rest_len: usize| input[input.len() - rest_len..].to_string();
+            return seed.map(|(out, rest_len)| (out, rest(rest_len)));
+        }
+
+        // Any recursive call at this position will fail, so that the non left recursive
+        // branches are tried, this gives the first seed
+        seeds.borrow_mut().insert(
+            key,
+            Err(ParsingError::PatternNotFound("left recursion".to_string())),
+        );
+
+        let mut best = self.parse_body(input, state);
+        while let Ok((out, rest)) = &best {
+            seeds
+                .borrow_mut()
+                .insert(key, Ok((out.clone(), rest.len())));
+
+            let snapshot = state.snapshot();
+            match self.parse_body(input, state) {
+                // Only keep growing while more input is consumed
+                Ok(grown) if grown.1.len() < rest.len() => best = Ok(grown),
+                _ => {
+                    state.restore(snapshot);
+                    break;
+                }
+            }
+        }
+
+        seeds.borrow_mut().remove(&key);
+        best
+    }
+
+    fn describe(&self) -> Description {
+        let name = self.name.clone().unwrap_or_else(|| "rule".to_string());
+        if self.describing.get() {
+            return Description::Rule(name);
+        }
+
+        self.describing.set(true);
+        let body = match self.body.borrow().as_ref() {
+            Some(body) => body.describe(),
+            None => Description::Predicate("undefined rule".to_string()),
+        };
+        self.describing.set(false);
+        match &self.name {
+            Some(name) => Description::named(name.clone(), body),
+            None => body,
+        }
+    }
+}
+
+/// A handle to a rule that does not keep it alive, see `Rule::downgrade`. Parsing fails once
+/// the rule has been dropped.
+pub struct WeakRule<'a, O> {
+    body: Weak<RefCell<Option<BoxedParser<'a, O>>>>,
+    seeds: Option<Weak<RefCell<Seeds<O>>>>,
+    name: Option<String>,
+    describing: Weak<Cell<bool>>,
+}
+
+impl<'a, O> Clone for WeakRule<'a, O> {
+    fn clone(&self) -> Self {
+        Self {
+            body: self.body.clone(),
+            seeds: self.seeds.clone(),
+            name: self.name.clone(),
+            describing: self.describing.clone(),
+        }
+    }
+}
+
+impl<'a, O> WeakRule<'a, O> {
+    /// The rule, if it has not been dropped
+    pub fn upgrade(&self) -> Option<Rule<'a, O>> {
+        let seeds = match &self.seeds {
+            Some(seeds) => Some(seeds.upgrade()?),
+            None => None,
+        };
+        Some(Rule {
+            body: self.body.upgrade()?,
+            seeds,
+            name: self.name.clone(),
+            describing: self.describing.upgrade()?,
+        })
+    }
+}
+
+impl<'a, O> Parser for WeakRule<'a, O>
+where
+    O: Debug + Clone,
+{
+    type Output = O;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        match self.upgrade() {
+            Some(rule) => rule.parse_stateful(input, state),
+            None => Err(ParsingError::PatternNotFound(
+                "rule was used after being dropped".to_string(),
+            )),
+        }
+    }
+
+    fn describe(&self) -> Description {
+        match self.upgrade() {
+            Some(rule) => rule.describe(),
+            None => Description::Rule(self.name.clone().unwrap_or_else(|| "rule".to_string())),
+        }
+    }
+}
+
+/// Make a rule that refers to itself
+///
+/// # Example
+///
+/// ```rust
+/// use mini_parc::parsers::{rule_p::{recursive, Rule}, ParseMatch};
+/// use mini_parc::traits::Parser;
+///
+/// let as_then_b = recursive(|rule: Rule<String>| {
+///     ParseMatch("a")
+///         .and_then(rule)
+///         .with_mapping(&|(a, rest): (String, String)| a + &rest)
+///         .otherwise(ParseMatch("b"))
+/// });
+/// assert_eq!(as_then_b.parse("aab!"), Ok(("aab".to_string(), "!".to_string())));
+/// ```
+pub fn recursive<'a, O, P, F>(f: F) -> Rule<'a, O>
+where
+    F: FnOnce(Rule<'a, O>) -> P,
+    P: Parser<Output = O> + 'a,
+{
+    let rule = Rule::new();
+    rule.define(f(rule.clone()));
+    rule
+}
+
+/// Make a rule that refers to itself, and may be left recursive, see
+/// `Rule::left_recursive`
+pub fn left_recursive<'a, O, P, F>(f: F) -> Rule<'a, O>
+where
+    F: FnOnce(Rule<'a, O>) -> P,
+    P: Parser<Output = O> + 'a,
+{
+    let rule = Rule::left_recursive();
+    rule.define(f(rule.clone()));
+    rule
+}
+
+#[cfg(test)]
+mod rule_parser_test {
+    use super::{left_recursive, Rule};
+    use crate::parsers::{ParseMatch, ParseWhile};
+    use crate::traits::Parser;
+
+    fn number() -> impl Parser<Output = i64> {
+        ParseWhile(|c| c.is_numeric()).with_try_mapping(&|s| s.parse().ok())
+    }
+
+    #[test]
+    fn weak_handles() {
+        // nested = "(" nested ")" | "x", without a reference cycle
+        let nested: Rule<usize> = Rule::new();
+        nested.define(
+            ParseMatch("(")
+                .and_then(nested.downgrade())
+                .and_then(ParseMatch(")"))
+                .with_mapping(&|((_, depth), _)| depth + 1)
+                .otherwise(ParseMatch("x").value(0)),
+        );
+        assert_eq!(nested.parse("((x))"), Ok((2, String::new())));
+
+        let weak = nested.downgrade();
+        assert!(weak.parse("(x)").is_ok());
+        drop(nested);
+        assert!(weak.upgrade().is_none());
+        assert!(weak.parse("x").is_err());
+    }
+
+    #[test]
+    fn direct_left_recursion() {
+        // expr = expr "-" number | number
+        let expr = left_recursive(|expr| {
+            expr.and_then(ParseMatch("-"))
+                .and_then(number())
+                .with_mapping(&|((a, _), b)| a - b)
+                .otherwise(number())
+        });
+
+        // Subtraction should be left associative
+        assert_eq!(expr.parse("10-2-3 rest"), Ok((5, " rest".to_string())));
+        assert_eq!(expr.parse("7"), Ok((7, String::new())));
+        assert!(expr.parse("-7").is_err());
+    }
+
+    #[test]
+    fn indirect_left_recursion() {
+        // sum = product "+" number | product ; product = sum "*" number | number
+        let sum: Rule<i64> = Rule::left_recursive();
+        let product: Rule<i64> = Rule::new();
+        product.define(
+            sum.clone()
+                .and_then(ParseMatch("*"))
+                .and_then(number())
+                .with_mapping(&|((a, _), b)| a * b)
+                .otherwise(number()),
+        );
+        sum.define(
+            product
+                .clone()
+                .and_then(ParseMatch("+"))
+                .and_then(number())
+                .with_mapping(&|((a, _), b)| a + b)
+                .otherwise(product.clone()),
+        );
+
+        // ((1 + 2) * 3) + 4
+        assert_eq!(sum.parse("1+2*3+4"), Ok((13, String::new())));
+    }
+
+    #[test]
+    fn undefined_rule() {
+        let rule: Rule<String> = Rule::new();
+        assert!(rule.parse("abc").is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt::Debug;
+
+use crate::{
+    describe::Description, errors::ParsingError, state::ParseState, traits::Parser,
+    type_alias::ParserRes,
+};
+
+pub(crate) fn downcast_state<S>(state: &mut dyn ParseState) -> Result<&mut S, ParsingError>
+where
+    S: 'static,
+{
+    state
+        .as_any_mut()
+        .downcast_mut::<S>()
+        .ok_or_else(|| ParsingError::MappingError("state has the wrong type".to_string()))
+}
+
+pub(crate) fn missing_state() -> ParsingError {
+    ParsingError::MappingError("parser requires a state, try using parse_with".to_string())
+}
+
+/// Given a parser with output of type K, and a mapping (K, &mut S) -> Z, make a new parser
+/// with output of type Z.
+///
+/// The mapping can read and update the user state, so it can be used to record declarations
+/// (such as typedef names) as they are parsed. This parser can only be run with a state of
+/// type S, see `Parser::parse_with`.
+pub struct StateMapParser<'a, P: Parser, S, T> {
+    pub parser: P,
+    pub mapping: &'a dyn Fn(P::Output, &mut S) -> T,
+}
+
+/// Like `StateMapParser`, but the mapping may fail, in which case the parser fails too.
+///
+/// This is useful to only accept something depending on the state, such as identifiers that
+/// were declared as type names.
+pub struct StateTryMapParser<'a, P: Parser, S, T> {
+    pub parser: P,
+    pub try_map: &'a dyn Fn(P::Output, &mut S) -> Option<T>,
+}
+
+impl<'a, P, S, T> Parser for StateMapParser<'a, P, S, T>
+where
+    P: Parser,
+    S: 'static,
+    T: Debug,
+{
+    type Output = T;
+    fn parse(&self, _: &str) -> ParserRes<Self::Output> {
+        Err(missing_state())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (p, rest) = self.parser.parse_stateful(input, state)?;
+        let state = downcast_state::<S>(state)?;
+        Ok(((self.mapping)(p, state), rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+impl<'a, P, S, T> Parser for StateTryMapParser<'a, P, S, T>
+where
+    P: Parser,
+    S: 'static,
+    T: Debug,
+{
+    type Output = T;
+    fn parse(&self, _: &str) -> ParserRes<Self::Output> {
+        Err(missing_state())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (p, rest) = self.parser.parse_stateful(input, state)?;
+        let state = downcast_state::<S>(state)?;
+        match (self.try_map)(p, state) {
+            None => Err(ParsingError::MappingError("mapping failed".to_string())),
+            Some(mapped_val) => Ok((mapped_val, rest)),
+        }
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+#[cfg(test)]
+mod state_parser_test {
+    use crate::parsers::and_p::KeepSecondOutputOnly;
+    use crate::parsers::repeat_p::RepeatParser;
+    use crate::parsers::{ParseMatch, ParseWhile, ParseWhileOrNothing};
+    use crate::traits::Parser;
+
+    fn word() -> impl Parser<Output = String> {
+        ParseWhileOrNothing(|c| c.is_whitespace())
+            .and_then(ParseWhile(|c| c.is_alphabetic()))
+            .combine(KeepSecondOutputOnly)
+    }
+
+    #[test]
+    fn symbol_table() {
+        // "type <name>" declares a type, any other word must be a declared type
+        let declare = ParseMatch("type")
+            .and_then(word())
+            .combine(KeepSecondOutputOnly)
+            .with_state_mapping(&|name: String, types: &mut Vec<String>| {
+                types.push(name.clone());
+                format!("declared {name}")
+            });
+        let use_type = word().with_state_try_mapping(&|name: String, types: &mut Vec<String>| {
+            types.contains(&name).then(|| format!("used {name}"))
+        });
+        let statement = ParseWhileOrNothing(|c| c.is_whitespace())
+            .and_then(declare.otherwise(use_type))
+            .combine(KeepSecondOutputOnly);
+        let program = RepeatParser::new(statement);
+
+        let mut types: Vec<String> = vec![];
+        let (out, _) = program
+            .parse_with("type foo foo type bar foo bar", &mut types)
+            .unwrap();
+        assert_eq!(out.len(), 5);
+        assert_eq!(types, vec!["foo".to_string(), "bar".to_string()]);
+
+        let mut types: Vec<String> = vec![];
+        let (out, _) = program.parse_with("type foo bar", &mut types).unwrap();
+        assert_eq!(out, vec!["declared foo".to_string()]);
+    }
+
+    #[test]
+    fn state_is_restored_on_backtrack() {
+        let count = ParseMatch("a").with_state_mapping(&|a, n: &mut usize| {
+            *n += 1;
+            a
+        });
+        let p = count
+            .and_then(ParseMatch("b"))
+            .otherwise(ParseMatch("a").and_then(ParseMatch("c")));
+
+        let mut n: usize = 0;
+        assert!(p.parse_with("ac", &mut n).is_ok());
+        assert_eq!(n, 0);
+        assert!(p.parse_with("ab", &mut n).is_ok());
+        assert_eq!(n, 1);
+    }
+
+    #[test]
+    fn state_is_required() {
+        let p = ParseMatch("a").with_state_mapping(&|a, n: &mut usize| {
+            *n += 1;
+            a
+        });
+        assert!(p.parse("a").is_err());
+        assert!(p.parse_with("a", &mut "wrong type").is_err());
+    }
+}
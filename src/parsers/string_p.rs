@@ -1,4 +1,4 @@
-use crate::traits::Parser;
+use crate::{describe::Description, traits::Parser};
 
 struct StringParser;
 
@@ -19,9 +19,12 @@ impl Parser for StringParser {
             match chars.next() {
                 Some('"') => break,
                 Some('\\') => {
-                    let char_after = chars.next().unwrap();
                     acc.push('\\');
-                    acc.push(char_after);
+                    // A backslash at the end of the input does not escape anything, so the
+                    // closing quote is still missing
+                    if let Some(char_after) = chars.next() {
+                        acc.push(char_after);
+                    }
                 }
                 Some(c) => acc.push(c),
                 None => {
@@ -34,6 +37,10 @@ impl Parser for StringParser {
 
         Ok((acc, chars.collect()))
     }
+
+    fn describe(&self) -> Description {
+        Description::Predicate("string".to_string())
+    }
 }
 
 pub fn string_parser() -> impl Parser<Output = String> {
@@ -42,6 +49,7 @@ pub fn string_parser() -> impl Parser<Output = String> {
 
 #[cfg(test)]
 mod string_parser_test {
+    use crate::errors::ParsingError;
     use crate::parsers::string_p::string_parser;
     use crate::traits::Parser;
 
@@ -57,4 +65,16 @@ mod string_parser_test {
             ))
         );
     }
+
+    #[test]
+    fn trailing_backslash() {
+        let sp = string_parser();
+        assert_eq!(
+            sp.parse("\"abc\\"),
+            Err(ParsingError::PatternNotFound(
+                "Did not find closing quote \"".to_string()
+            ))
+        );
+        assert!(sp.parse("\"\\").is_err());
+    }
 }
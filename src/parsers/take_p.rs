@@ -0,0 +1,314 @@
+//! Parsers that take a bounded amount of input: a fixed count (`take_n`), everything up to a
+//! delimiter (`take_until`), or between `m` and `n` characters that meet a predicate
+//! (`take_while_m_n`).
+//!
+//! Counts are in characters, or in bytes after calling `.bytes()`. When too little input is
+//! taken, the error is a `ParsingError::TooFew`, which `parse_complete` turns
+//! into a `ParsingError::Located`, so that it says where it happened.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::parsers::{take_p::{take_n, take_until, take_while_m_n}, ParseMatch};
+//! use mini_parc::traits::Parser;
+//!
+//! let escape = ParseMatch("\\u").and_then(take_while_m_n(4, 4, |c| c.is_ascii_hexdigit()));
+//! assert_eq!(escape.parse("\\u00e9!").unwrap().0 .1, "00e9");
+//!
+//! let comment = ParseMatch("<!--").and_then(take_until("-->")).and_then(ParseMatch("-->"));
+//! assert_eq!(comment.parse("<!-- a -- b -->").unwrap().0 .0 .1, " a -- b ");
+//!
+//! assert_eq!(take_n(2).parse("été"), Ok(("ét".to_string(), "é".to_string())));
+//! assert_eq!(take_n(3).bytes().parse("été"), Ok(("ét".to_string(), "é".to_string())));
+//!
+//! let day = ParseMatch("date: ").and_then(take_while_m_n(1, 2, |c| c.is_ascii_digit()).named("digit"));
+//! assert_eq!(
+//!     day.parse_complete("date: x").unwrap_err().to_string(),
+//!     "expected at least 1 digit, found 0 at line 1, column 7"
+//! );
+//! ```
+
+use crate::{describe::Description, errors::ParsingError, traits::Parser, type_alias::ParserRes};
+
+/// What the counts of the take parsers are measured in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Chars,
+    Bytes,
+}
+
+impl Unit {
+    fn size(self, c: char) -> usize {
+        match self {
+            Unit::Chars => 1,
+            Unit::Bytes => c.len_utf8(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Unit::Chars => "character",
+            Unit::Bytes => "byte",
+        }
+    }
+}
+
+/// Take exactly `count` characters (or bytes), see `take_n`
+#[derive(Debug, Clone)]
+pub struct TakeN {
+    pub count: usize,
+    pub unit: Unit,
+}
+
+impl TakeN {
+    /// Count bytes instead of characters. Taking the bytes must not split a character.
+    pub fn bytes(self) -> Self {
+        Self {
+            unit: Unit::Bytes,
+            ..self
+        }
+    }
+}
+
+impl Parser for TakeN {
+    type Output = String;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        let too_few = |found| ParsingError::TooFew {
+            expected: self.unit.name().to_string(),
+            min: self.count,
+            found,
+            remaining: input.len(),
+        };
+        let end = match self.unit {
+            Unit::Chars => match input.char_indices().nth(self.count) {
+                Some((end, _)) => end,
+                None if input.chars().count() == self.count => input.len(),
+                None => return Err(too_few(input.chars().count())),
+            },
+            Unit::Bytes if input.len() < self.count => return Err(too_few(input.len())),
+            Unit::Bytes => self.count,
+        };
+        if !input.is_char_boundary(end) {
+            let split = input
+                .char_indices()
+                .find(|(i, c)| i + c.len_utf8() > end)
+                .map(|(_, c)| c)
+                .unwrap_or_default();
+            return Err(ParsingError::PatternNotFound(format!(
+                "taking {} bytes would split {split:?}",
+                self.count
+            )));
+        }
+        Ok((input[..end].to_string(), input[end..].to_string()))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Predicate(format!("{} {}s", self.count, self.unit.name()))
+    }
+}
+
+/// Take exactly `count` characters
+pub fn take_n(count: usize) -> TakeN {
+    TakeN {
+        count,
+        unit: Unit::Chars,
+    }
+}
+
+/// Take everything before a delimiter, see `take_until`
+#[derive(Debug, Clone)]
+pub struct TakeUntil {
+    pub delimiter: String,
+}
+
+impl Parser for TakeUntil {
+    type Output = String;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        match input.find(&self.delimiter) {
+            Some(end) => Ok((input[..end].to_string(), input[end..].to_string())),
+            None => Err(ParsingError::PatternNotFound(format!(
+                "expected {:?}, found the end of the input",
+                self.delimiter
+            ))),
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::Predicate(format!("text until {:?}", self.delimiter))
+    }
+}
+
+/// Take everything before a delimiter, which is not consumed. Fails if the delimiter is not
+/// found.
+pub fn take_until<S>(delimiter: S) -> TakeUntil
+where
+    S: Into<String>,
+{
+    TakeUntil {
+        delimiter: delimiter.into(),
+    }
+}
+
+/// Take between `min` and `max` characters (or bytes) that meet a predicate, see
+/// `take_while_m_n`
+#[derive(Debug, Clone)]
+pub struct TakeWhileMN<F>
+where
+    F: Fn(char) -> bool,
+{
+    pub min: usize,
+    pub max: usize,
+    pub predicate: F,
+    /// What one of the characters is called in errors, such as "digit"
+    pub name: String,
+    pub unit: Unit,
+}
+
+impl<F> TakeWhileMN<F>
+where
+    F: Fn(char) -> bool,
+{
+    /// Call the characters something more specific than "character" in errors
+    pub fn named<S>(self, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            ..self
+        }
+    }
+
+    /// Count bytes instead of characters. A character that would go over `max` bytes is not
+    /// taken.
+    pub fn bytes(self) -> Self {
+        Self {
+            unit: Unit::Bytes,
+            ..self
+        }
+    }
+}
+
+impl<F> Parser for TakeWhileMN<F>
+where
+    F: Fn(char) -> bool,
+{
+    type Output = String;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        let (mut end, mut taken) = (0, 0);
+        for c in input.chars() {
+            let size = self.unit.size(c);
+            if taken + size > self.max || !(self.predicate)(c) {
+                break;
+            }
+            taken += size;
+            end += c.len_utf8();
+        }
+        if taken < self.min {
+            return Err(ParsingError::TooFew {
+                expected: self.name.clone(),
+                min: self.min,
+                found: taken,
+                remaining: input.len(),
+            });
+        }
+        Ok((input[..end].to_string(), input[end..].to_string()))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Predicate(format!("{} to {} {}s", self.min, self.max, self.name))
+    }
+}
+
+/// Take between `min` and `max` characters that meet a predicate
+pub fn take_while_m_n<F>(min: usize, max: usize, predicate: F) -> TakeWhileMN<F>
+where
+    F: Fn(char) -> bool,
+{
+    TakeWhileMN {
+        min,
+        max,
+        predicate,
+        name: Unit::Chars.name().to_string(),
+        unit: Unit::Chars,
+    }
+}
+
+#[cfg(test)]
+mod take_parser_test {
+    use super::{take_n, take_until, take_while_m_n};
+    use crate::errors::ParsingError;
+    use crate::parsers::{class_p::CharSet, ParseMatch};
+    use crate::traits::Parser;
+
+    #[test]
+    fn counts_in_chars_and_bytes() {
+        assert_eq!(take_n(0).parse("ab"), Ok((String::new(), "ab".to_string())));
+        assert_eq!(take_n(2).parse("€€"), Ok(("€€".to_string(), String::new())));
+        assert_eq!(
+            take_n(3).parse("€€"),
+            Err(ParsingError::TooFew {
+                expected: "character".to_string(),
+                min: 3,
+                found: 2,
+                remaining: 6
+            })
+        );
+        assert_eq!(
+            take_n(3).bytes().parse("€€"),
+            Ok(("€".to_string(), "€".to_string()))
+        );
+        assert_eq!(
+            take_n(4).bytes().parse("€€"),
+            Err(ParsingError::PatternNotFound(
+                "taking 4 bytes would split '€'".to_string()
+            ))
+        );
+        assert_eq!(
+            take_n(7).bytes().parse("€€").unwrap_err().to_string(),
+            "expected at least 7 bytes, found 6"
+        );
+    }
+
+    #[test]
+    fn until_a_delimiter() {
+        let p = take_until("*/");
+        assert_eq!(
+            p.parse("a * b */ c"),
+            Ok(("a * b ".to_string(), "*/ c".to_string()))
+        );
+        assert_eq!(p.parse("*/"), Ok((String::new(), "*/".to_string())));
+        assert_eq!(
+            p.parse("a * b"),
+            Err(ParsingError::PatternNotFound(
+                "expected \"*/\", found the end of the input".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn between_m_and_n() {
+        let octal = CharSet::from('0'..='7');
+        let p = take_while_m_n(1, 3, octal.predicate()).named("octal digit");
+        assert_eq!(p.parse("7777"), Ok(("777".to_string(), "7".to_string())));
+        assert_eq!(p.parse("78"), Ok(("7".to_string(), "8".to_string())));
+
+        let p = ParseMatch("\\\n")
+            .and_then(take_while_m_n(2, 3, octal.predicate()).named("octal digit"));
+        assert_eq!(
+            p.parse_complete("\\\n19").unwrap_err().to_string(),
+            "expected at least 2 octal digits, found 1 at line 2, column 1"
+        );
+        // Errors keep their position through context frames
+        let p = p.context("in an escape");
+        assert_eq!(
+            p.parse_complete("\\\n19").unwrap_err().to_string(),
+            "expected at least 2 octal digits, found 1 at line 2, column 1, in an escape"
+        );
+
+        // `é` is two bytes, so it does not fit after `a`
+        let p = take_while_m_n(1, 2, |c: char| c.is_alphabetic()).bytes();
+        assert_eq!(p.parse("aé"), Ok(("a".to_string(), "é".to_string())));
+        assert_eq!(p.parse("éa"), Ok(("é".to_string(), "a".to_string())));
+    }
+}
@@ -0,0 +1,327 @@
+//! Tracing of parsers, enabled with the `trace` feature.
+//!
+//! Parsers wrapped with `Parser::trace` record when they are entered and exited, while trace
+//! mode is on (see `start` and `finish`). Without the `trace` feature, `TraceParser` just
+//! runs the inner parser, and `finish` always returns an empty trace.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::parsers::{trace_p, ParseMatch, ParseWhile};
+//! use mini_parc::traits::Parser;
+//!
+//! let number = ParseWhile(|c| c.is_numeric()).trace("number");
+//! let sum = number
+//!     .and_then(ParseMatch("+"))
+//!     .and_then(ParseWhile(|c| c.is_numeric()).trace("number"))
+//!     .trace("sum");
+//!
+//! trace_p::start("1+x");
+//! assert!(sum.parse("1+x").is_err());
+//! let trace = trace_p::finish();
+//!
+//! # #[cfg(feature = "trace")]
+//! assert_eq!(
+//!     trace.to_string(),
+//!     [
+//!         "sum at line 1, column 1: \"1+x\"",
+//!         "  number at line 1, column 1: \"1+x\"",
+//!         "  ok number, consumed 1 bytes",
+//!         "  number at line 1, column 3: \"x\"",
+//!         "  failed number: no characters matched predicate",
+//!         "failed sum: no characters matched predicate",
+//!         "",
+//!     ]
+//!     .join("\n")
+//! );
+//! ```
+
+use std::fmt::Display;
+
+use crate::{
+    describe::Description, position::Position, state::ParseState, traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// How many characters of the input are shown when a parser is entered
+const PREVIEW_CHARS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEventKind {
+    /// The parser was entered, with a preview of the input
+    Enter { preview: String },
+    /// The parser succeeded
+    Success { consumed: usize },
+    /// The parser failed, with the error message
+    Failure { error: String },
+}
+
+/// A traced parser being entered or exited
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEvent {
+    pub name: String,
+    /// How many traced parsers this one is nested in
+    pub depth: usize,
+    /// Where the parser was entered
+    pub position: Position,
+    pub kind: TraceEventKind,
+}
+
+/// The events recorded between `start` and `finish`.
+///
+/// Displaying a trace gives an indented tree, one line per event, and `to_json` gives the
+/// events as a JSON array, for tools.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trace {
+    pub events: Vec<TraceEvent>,
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for event in &self.events {
+            let indent = "  ".repeat(event.depth);
+            let name = &event.name;
+            match &event.kind {
+                TraceEventKind::Enter { preview } => {
+                    writeln!(f, "{indent}{name} at {}: {preview:?}", event.position)?
+                }
+                TraceEventKind::Success { consumed } => {
+                    writeln!(f, "{indent}ok {name}, consumed {consumed} bytes")?
+                }
+                TraceEventKind::Failure { error } => writeln!(f, "{indent}failed {name}: {error}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Quote a string for JSON
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl Trace {
+    /// The events as a JSON array of objects, with the fields `event` ("enter", "success"
+    /// or "failure"), `name`, `depth`, `offset`, `line`, `column`, and one of `preview`,
+    /// `consumed` or `error`
+    pub fn to_json(&self) -> String {
+        let events: Vec<String> = self
+            .events
+            .iter()
+            .map(|e| {
+                let (event, detail) = match &e.kind {
+                    TraceEventKind::Enter { preview } => {
+                        ("enter", format!("\"preview\":{}", json_string(preview)))
+                    }
+                    TraceEventKind::Success { consumed } => {
+                        ("success", format!("\"consumed\":{consumed}"))
+                    }
+                    TraceEventKind::Failure { error } => {
+                        ("failure", format!("\"error\":{}", json_string(error)))
+                    }
+                };
+                format!(
+                    "{{\"event\":\"{event}\",\"name\":{},\"depth\":{},\"offset\":{},\"line\":{},\"column\":{},{detail}}}",
+                    json_string(&e.name),
+                    e.depth,
+                    e.position.offset,
+                    e.position.line,
+                    e.position.column,
+                )
+            })
+            .collect();
+        format!("[{}]", events.join(","))
+    }
+}
+
+#[cfg(feature = "trace")]
+mod recorder {
+    use std::cell::RefCell;
+
+    use super::{Trace, TraceEvent, TraceEventKind, PREVIEW_CHARS};
+    use crate::position::Position;
+
+    struct Recorder {
+        source: String,
+        depth: usize,
+        trace: Trace,
+    }
+
+    thread_local! {
+        static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+    }
+
+    pub fn start(source: &str) {
+        RECORDER.with(|r| {
+            *r.borrow_mut() = Some(Recorder {
+                source: source.to_string(),
+                depth: 0,
+                trace: Trace::default(),
+            })
+        });
+    }
+
+    pub fn finish() -> Trace {
+        RECORDER.with(|r| r.borrow_mut().take().map(|r| r.trace).unwrap_or_default())
+    }
+
+    /// Record that a parser was entered, returns `false` when trace mode is off
+    pub fn enter(name: &str, input: &str) -> bool {
+        RECORDER.with(|r| {
+            let mut r = r.borrow_mut();
+            let Some(r) = r.as_mut() else {
+                return false;
+            };
+            let event = TraceEvent {
+                name: name.to_string(),
+                depth: r.depth,
+                position: Position::locate(&r.source, input),
+                kind: TraceEventKind::Enter {
+                    preview: input.chars().take(PREVIEW_CHARS).collect(),
+                },
+            };
+            r.trace.events.push(event);
+            r.depth += 1;
+            true
+        })
+    }
+
+    pub fn exit(name: &str, input: &str, kind: TraceEventKind) {
+        RECORDER.with(|r| {
+            if let Some(r) = r.borrow_mut().as_mut() {
+                r.depth = r.depth.saturating_sub(1);
+                let event = TraceEvent {
+                    name: name.to_string(),
+                    depth: r.depth,
+                    position: Position::locate(&r.source, input),
+                    kind,
+                };
+                r.trace.events.push(event);
+            }
+        });
+    }
+}
+
+/// Turn trace mode on for this thread, clearing any previous events. `source` is the
+/// whole input, which is used to find the positions of the events.
+pub fn start(source: &str) {
+    #[cfg(feature = "trace")]
+    recorder::start(source);
+    #[cfg(not(feature = "trace"))]
+    let _ = source;
+}
+
+/// Turn trace mode off for this thread, returning the events recorded since `start`
+pub fn finish() -> Trace {
+    #[cfg(feature = "trace")]
+    return recorder::finish();
+    #[cfg(not(feature = "trace"))]
+    Trace::default()
+}
+
+/// Record when the inner parser is entered and exited, see `Parser::trace`
+pub struct TraceParser<P> {
+    pub parser: P,
+    pub name: String,
+}
+
+impl<P> Parser for TraceParser<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    #[cfg(not(feature = "trace"))]
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        self.parser.parse_stateful(input, state)
+    }
+
+    #[cfg(feature = "trace")]
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        if !recorder::enter(&self.name, input) {
+            return self.parser.parse_stateful(input, state);
+        }
+
+        let res = self.parser.parse_stateful(input, state);
+        let kind = match &res {
+            Ok((_, rest)) => TraceEventKind::Success {
+                consumed: input.len() - rest.len(),
+            },
+            Err(e) => TraceEventKind::Failure {
+                error: e.to_string(),
+            },
+        };
+        recorder::exit(&self.name, input, kind);
+        res
+    }
+
+    fn describe(&self) -> Description {
+        Description::named(self.name.clone(), self.parser.describe())
+    }
+}
+
+#[cfg(all(test, feature = "trace"))]
+mod trace_parser_test {
+    use super::{finish, start, TraceEventKind};
+    use crate::parsers::{rule_p::recursive, ParseMatch};
+    use crate::traits::Parser;
+
+    #[test]
+    fn nested_rules() {
+        // nested = "(" nested ")" | "x"
+        let nested = recursive(|nested| {
+            ParseMatch("(")
+                .and_then(nested)
+                .and_then(ParseMatch(")"))
+                .with_mapping(&|((_, inner), _)| inner)
+                .otherwise(ParseMatch("x"))
+                .trace("nested")
+        });
+
+        start("((x))");
+        assert!(nested.parse("((x))").is_ok());
+        let trace = finish();
+
+        let depths: Vec<usize> = trace.events.iter().map(|e| e.depth).collect();
+        assert_eq!(depths, [0, 1, 2, 2, 1, 0]);
+        assert_eq!(trace.events[1].position.column, 2);
+        assert_eq!(
+            trace.events.last().unwrap().kind,
+            TraceEventKind::Success { consumed: 5 }
+        );
+
+        // Trace mode is off again
+        assert!(nested.parse("x").is_ok());
+        assert!(finish().events.is_empty());
+    }
+
+    #[test]
+    fn json_output() {
+        let quote = ParseMatch("\"a\"").trace("quote");
+        start("\"b\"\n");
+        assert!(quote.parse("\"b\"\n").is_err());
+
+        let json = finish().to_json();
+        assert!(json.starts_with(
+            r#"[{"event":"enter","name":"quote","depth":0,"offset":0,"line":1,"column":1,"preview":"\"b\"\n"}"#
+        ));
+        assert!(json.contains(r#""event":"failure""#));
+        assert!(json.ends_with("}]"));
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt::Display;
+
+/// A location within some input string. Lines and columns start at 1, and columns are
+/// counted in characters (not bytes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// Offset in bytes from the start of the input
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// Find where `rest` starts within `input`.
+    ///
+    /// Every parser returns the rest of the input as a suffix of what it was given, so the
+    /// position of the rest can be found by its length. If `rest` is longer than the input,
+    /// the start of the input is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mini_parc::parsers::ParseMatch;
+    /// use mini_parc::position::Position;
+    /// use mini_parc::traits::Parser;
+    ///
+    /// let input = "if\n  x";
+    /// let (_, rest) = ParseMatch("if\n  ").parse(input).unwrap();
+    /// let pos = Position::locate(input, &rest);
+    /// assert_eq!((pos.line, pos.column), (2, 3));
+    /// ```
+    pub fn locate(input: &str, rest: &str) -> Self {
+        let mut offset = input.len().saturating_sub(rest.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let consumed = &input[..offset];
+        let line = consumed.matches('\n').count() + 1;
+        let line_start = consumed.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let column = consumed[line_start..].chars().count() + 1;
+        Self {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
@@ -0,0 +1,758 @@
+//! Grammars that are loaded at runtime, from EBNF or PEG text.
+//!
+//! A `Grammar` is parsed (with `mini_parc` itself) from the text of its rules, and each rule
+//! becomes a boxed parser. Parsing with a rule gives a generic concrete syntax tree: every
+//! rule that matches makes a `Node`, with the rules it used as children. Literals and
+//! character classes do not make nodes, their text can be found from the span of the node
+//! that matched them.
+//!
+//! Both syntaxes share most of their notation:
+//! - A rule is `name = expression ;`. PEG grammars may also use `<-`, and EBNF grammars
+//!   `::=`. The `;` is optional.
+//! - Alternatives are separated by `|` (or `/` in PEG) and tried in order, the first one that
+//!   matches is used.
+//! - Sequences are written by juxtaposition (EBNF may separate items with `,`).
+//! - `"text"` and `'text'` are literals, and `'a'..'z'` is a range of characters.
+//! - `( ... )` groups, and items can be followed by `*`, `+` or `?`, and preceded by `&`
+//!   (look ahead) or `!` (negative look ahead).
+//! - Comments start with `#` or `//`, or are enclosed in `(* ... *)`.
+//!
+//! In PEG grammars, `[a-z_]` is a character class (`[^...]` to negate it), and `.` matches
+//! any character. In EBNF grammars, `[ ... ]` is optional and `{ ... }` is repeated zero or
+//! more times.
+//!
+//! Left recursive rules, such as `sum = sum "+" num | num`, are supported (see
+//! `Rule::left_recursive`).
+//!
+//! # Example
+//!
+//! ```rust
+//! use mini_parc::runtime_grammar::Grammar;
+//! use mini_parc::traits::Parser;
+//!
+//! let grammar = Grammar::peg(
+//!     r#"
+//!     pair  <- key ws "=" ws value
+//!     key   <- [a-z]+
+//!     value <- [0-9]+ / key
+//!     ws    <- " "*
+//!     "#,
+//! )
+//! .unwrap();
+//!
+//! let input = "port = 80";
+//! let pair = grammar.parser("pair").unwrap().parse_complete(input).unwrap();
+//! assert_eq!(pair.rule, "pair");
+//! assert_eq!(pair.span, 0..9);
+//!
+//! let names: Vec<&str> = pair.children.iter().map(|n| n.rule.as_str()).collect();
+//! assert_eq!(names, ["key", "ws", "ws", "value"]);
+//! assert_eq!(pair.children[3].text(input), "80");
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    rc::Rc,
+};
+
+use crate::{
+    describe::{CharPredicate, Description},
+    errors::ParsingError,
+    parsers::{
+        and_p::{KeepFirstOutputOnly, KeepSecondOutputOnly},
+        look_p::{not, peek},
+        repeat_p::RepeatParser,
+        rule_p::{BoxedParser, Rule},
+        ParseIf, ParseMatch, ParseWhile, ParseWhileOrNothing,
+    },
+    state::ParseState,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// A node of the concrete syntax tree, made by a rule that matched
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    /// The name of the rule
+    pub rule: String,
+    /// Where the rule matched, in bytes, in the input given to the parser
+    pub span: Range<usize>,
+    /// The nodes of the rules used by this rule, in order
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    /// The text that this node matched, `input` must be the input given to the parser
+    pub fn text<'i>(&self, input: &'i str) -> &'i str {
+        &input[self.span.clone()]
+    }
+
+    /// Turn the spans, from the length of the remaining input, into offsets
+    fn locate(&mut self, len: usize) {
+        self.span = len - self.span.start..len - self.span.end;
+        self.children.iter_mut().for_each(|c| c.locate(len));
+    }
+}
+
+/// The syntax of a grammar, see the module documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Peg,
+    Ebnf,
+}
+
+/// The right hand side of a rule
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(String),
+    /// Any character in one of the (inclusive) ranges, or not in any if `negated`
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Any,
+    Rule(String),
+    Sequence(Vec<Expr>),
+    Choice(Vec<Expr>),
+    Many(Box<Expr>),
+    Many1(Box<Expr>),
+    Optional(Box<Expr>),
+    Peek(Box<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    /// Whether this can match without consuming any input
+    fn nullable(&self, nullable_rules: &HashSet<String>) -> bool {
+        match self {
+            Expr::Literal(lit) => lit.is_empty(),
+            Expr::Class { .. } | Expr::Any => false,
+            Expr::Rule(name) => nullable_rules.contains(name),
+            Expr::Sequence(items) => items.iter().all(|e| e.nullable(nullable_rules)),
+            Expr::Choice(alts) => alts.iter().any(|e| e.nullable(nullable_rules)),
+            Expr::Many1(e) => e.nullable(nullable_rules),
+            Expr::Many(_) | Expr::Optional(_) | Expr::Peek(_) | Expr::Not(_) => true,
+        }
+    }
+
+    /// Rules that can be reached without consuming any input
+    fn leftmost_rules(&self, nullable_rules: &HashSet<String>, out: &mut HashSet<String>) {
+        match self {
+            Expr::Rule(name) => {
+                out.insert(name.clone());
+            }
+            Expr::Sequence(items) => {
+                for item in items {
+                    item.leftmost_rules(nullable_rules, out);
+                    if !item.nullable(nullable_rules) {
+                        break;
+                    }
+                }
+            }
+            Expr::Choice(alts) => alts
+                .iter()
+                .for_each(|e| e.leftmost_rules(nullable_rules, out)),
+            Expr::Many(e) | Expr::Many1(e) | Expr::Optional(e) | Expr::Peek(e) | Expr::Not(e) => {
+                e.leftmost_rules(nullable_rules, out)
+            }
+            Expr::Literal(_) | Expr::Class { .. } | Expr::Any => {}
+        }
+    }
+
+    fn referenced_rules<'e>(&'e self, out: &mut Vec<&'e String>) {
+        match self {
+            Expr::Rule(name) => out.push(name),
+            Expr::Sequence(items) | Expr::Choice(items) => {
+                items.iter().for_each(|e| e.referenced_rules(out))
+            }
+            Expr::Many(e) | Expr::Many1(e) | Expr::Optional(e) | Expr::Peek(e) | Expr::Not(e) => {
+                e.referenced_rules(out)
+            }
+            Expr::Literal(_) | Expr::Class { .. } | Expr::Any => {}
+        }
+    }
+}
+
+/// Parse one character that is (or is not) in some ranges
+struct ClassParser {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl Parser for ClassParser {
+    type Output = char;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        let Some(c) = input.chars().next() else {
+            return Err(ParsingError::CannotParseAnEmptyString);
+        };
+        let in_class = self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+        if in_class == self.negated {
+            return Err(ParsingError::PatternNotFound(format!(
+                "'{c}' is not in the character class"
+            )));
+        }
+        Ok((c, input[c.len_utf8()..].to_string()))
+    }
+
+    fn describe(&self) -> Description {
+        let ranges: String = self
+            .ranges
+            .iter()
+            .map(|&(lo, hi)| match lo == hi {
+                true => lo.to_string(),
+                false => format!("{lo}-{hi}"),
+            })
+            .collect();
+        let caret = if self.negated { "^" } else { "" };
+        let (class, negated) = (self.ranges.clone(), self.negated);
+        let test = move |c| class.iter().any(|&(lo, hi)| lo <= c && c <= hi) != negated;
+        Description::Char(CharPredicate::new(format!("[{caret}{ranges}]"), test))
+    }
+}
+
+/// Make a node out of the children of a rule. Until the whole tree is built, spans hold the
+/// length of the remaining input, rather than offsets.
+struct NodeParser {
+    rule: String,
+    body: BoxedParser<'static, Vec<Node>>,
+}
+
+impl Parser for NodeParser {
+    type Output = Node;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (children, rest) = self.body.parse_stateful(input, state)?;
+        let node = Node {
+            rule: self.rule.clone(),
+            span: input.len()..rest.len(),
+            children,
+        };
+        Ok((node, rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.body.describe()
+    }
+}
+
+/// Parser of one of the rules of a `Grammar`, see `Grammar::parser`
+pub struct GrammarParser {
+    rule: Rule<'static, Node>,
+    /// Rules only refer to each other weakly, so that recursive rules do not leak, this
+    /// keeps all of them alive
+    _rules: Rc<HashMap<String, Rule<'static, Node>>>,
+}
+
+impl Parser for GrammarParser {
+    type Output = Node;
+    fn parse(&self, input: &str) -> ParserRes<Self::Output> {
+        self.parse_stateful(input, &mut ())
+    }
+
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let (mut node, rest) = self.rule.parse_stateful(input, state)?;
+        node.locate(input.len());
+        Ok((node, rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.rule.describe()
+    }
+}
+
+/// A set of rules, loaded from the text of a grammar
+pub struct Grammar {
+    definitions: Vec<(String, Expr)>,
+    rules: Rc<HashMap<String, Rule<'static, Node>>>,
+}
+
+impl Grammar {
+    /// Load a grammar written as a PEG
+    pub fn peg(text: &str) -> Result<Self, ParsingError> {
+        Self::load(text, Syntax::Peg)
+    }
+
+    /// Load a grammar written in EBNF
+    pub fn ebnf(text: &str) -> Result<Self, ParsingError> {
+        Self::load(text, Syntax::Ebnf)
+    }
+
+    pub fn load(text: &str, syntax: Syntax) -> Result<Self, ParsingError> {
+        let definitions = grammar_parser(syntax).parse_complete(text)?;
+        Self::from_definitions(definitions)
+    }
+
+    /// Build a grammar from the rules, in the order that they were written
+    pub fn from_definitions(definitions: Vec<(String, Expr)>) -> Result<Self, ParsingError> {
+        let mut defined = HashSet::new();
+        for (name, expr) in &definitions {
+            if !defined.insert(name.as_str()) {
+                return Err(ParsingError::PatternNotFound(format!(
+                    "rule `{name}` is defined more than once"
+                )));
+            }
+
+            let mut referenced = vec![];
+            expr.referenced_rules(&mut referenced);
+            if let Some(undefined) = referenced
+                .into_iter()
+                .find(|r| !definitions.iter().any(|(name, _)| name == *r))
+            {
+                return Err(ParsingError::PatternNotFound(format!(
+                    "rule `{name}` uses undefined rule `{undefined}`"
+                )));
+            }
+        }
+
+        let left_recursive = left_recursive_rules(&definitions);
+        let rules: HashMap<String, Rule<'static, Node>> = definitions
+            .iter()
+            .map(|(name, _)| {
+                let rule = if left_recursive.contains(name) {
+                    Rule::left_recursive()
+                } else {
+                    Rule::new()
+                };
+                (name.clone(), rule.named(name.clone()))
+            })
+            .collect();
+
+        for (name, expr) in &definitions {
+            rules[name].define(NodeParser {
+                rule: name.clone(),
+                body: compile(expr, &rules),
+            });
+        }
+
+        Ok(Self {
+            definitions,
+            rules: Rc::new(rules),
+        })
+    }
+
+    /// The rules of the grammar, in the order that they were written
+    pub fn definitions(&self) -> &[(String, Expr)] {
+        &self.definitions
+    }
+
+    /// A parser for one of the rules, or `None` if there is no such rule
+    pub fn parser(&self, rule: &str) -> Option<GrammarParser> {
+        let rule = self.rules.get(rule)?.clone();
+        Some(GrammarParser {
+            rule,
+            _rules: self.rules.clone(),
+        })
+    }
+}
+
+fn left_recursive_rules(definitions: &[(String, Expr)]) -> HashSet<String> {
+    // Rules that may match nothing, found by iterating until nothing changes
+    let mut nullable = HashSet::new();
+    loop {
+        let before = nullable.len();
+        for (name, expr) in definitions {
+            if expr.nullable(&nullable) {
+                nullable.insert(name.clone());
+            }
+        }
+        if nullable.len() == before {
+            break;
+        }
+    }
+
+    let leftmost: HashMap<&String, HashSet<String>> = definitions
+        .iter()
+        .map(|(name, expr)| {
+            let mut out = HashSet::new();
+            expr.leftmost_rules(&nullable, &mut out);
+            (name, out)
+        })
+        .collect();
+
+    let mut recursive = HashSet::new();
+    for (rule, first) in &leftmost {
+        let mut seen = HashSet::new();
+        let mut stack: Vec<&String> = first.iter().collect();
+        while let Some(next) = stack.pop() {
+            if next == *rule {
+                recursive.insert((*rule).clone());
+                break;
+            }
+            if seen.insert(next) {
+                stack.extend(leftmost.get(next).into_iter().flatten());
+            }
+        }
+    }
+    recursive
+}
+
+/// Turn an expression into a parser of the nodes it makes
+fn compile(
+    expr: &Expr,
+    rules: &HashMap<String, Rule<'static, Node>>,
+) -> BoxedParser<'static, Vec<Node>> {
+    match expr {
+        Expr::Literal(lit) => ParseMatch(lit.clone()).value(vec![]).boxed(),
+        Expr::Class { ranges, negated } => ClassParser {
+            ranges: ranges.clone(),
+            negated: *negated,
+        }
+        .value(vec![])
+        .boxed(),
+        Expr::Any => ParseIf(|_| true).value(vec![]).boxed(),
+        Expr::Rule(name) => rules[name].downgrade().with_mapping(&|n| vec![n]).boxed(),
+        Expr::Sequence(items) => {
+            let mut parsers = items.iter().map(|e| compile(e, rules));
+            let first = parsers.next().unwrap_or_else(empty);
+            parsers.fold(first, |acc, p| {
+                acc.and_then(p)
+                    .with_mapping(&|(mut a, b): (Vec<Node>, Vec<Node>)| {
+                        a.extend(b);
+                        a
+                    })
+                    .boxed()
+            })
+        }
+        Expr::Choice(alts) => {
+            let mut parsers = alts.iter().map(|e| compile(e, rules));
+            let first = parsers.next().unwrap_or_else(empty);
+            parsers.fold(first, |acc, p| acc.otherwise(p).boxed())
+        }
+        Expr::Many(e) => many(compile(e, rules)).or_default().boxed(),
+        Expr::Many1(e) => many(compile(e, rules)).boxed(),
+        Expr::Optional(e) => compile(e, rules).or_default().boxed(),
+        Expr::Peek(e) => peek(compile(e, rules)).value(vec![]).boxed(),
+        Expr::Not(e) => not(compile(e, rules)).value(vec![]).boxed(),
+    }
+}
+
+/// Match nothing, making no nodes
+fn empty() -> BoxedParser<'static, Vec<Node>> {
+    ParseWhileOrNothing(|_| false).value(vec![]).boxed()
+}
+
+/// One or more matches, with all of their nodes
+fn many(parser: BoxedParser<'static, Vec<Node>>) -> impl Parser<Output = Vec<Node>> {
+    RepeatParser::new(parser).with_mapping(&|nodes: Vec<Vec<Node>>| nodes.concat())
+}
+
+/// Whitespace and comments
+fn spacing() -> BoxedParser<'static, ()> {
+    let line_comment = ParseMatch("#")
+        .otherwise(ParseMatch("//"))
+        .and_then(ParseWhileOrNothing(|c| c != '\n'))
+        .value(());
+    let block_comment = ParseMatch("(*")
+        .and_then(RepeatParser::new(not(ParseMatch("*)")).and_then(ParseIf(|_| true))).or_default())
+        .and_then(ParseMatch("*)"))
+        .value(());
+    RepeatParser::new(
+        ParseWhile(|c| c.is_whitespace())
+            .value(())
+            .otherwise(line_comment)
+            .otherwise(block_comment),
+    )
+    .value(())
+    .or_default()
+    .boxed()
+}
+
+/// Skip any spacing after the parser
+fn lexeme<P>(parser: P) -> BoxedParser<'static, P::Output>
+where
+    P: Parser + 'static,
+{
+    parser
+        .and_then(spacing())
+        .combine(KeepFirstOutputOnly)
+        .boxed()
+}
+
+fn token(text: &'static str) -> BoxedParser<'static, String> {
+    lexeme(ParseMatch(text))
+}
+
+fn ident() -> BoxedParser<'static, String> {
+    lexeme(
+        ParseIf(|c| c.is_alphabetic() || c == '_')
+            .and_then(ParseWhileOrNothing(|c| c.is_alphanumeric() || c == '_'))
+            .recognize(),
+    )
+}
+
+fn def_op() -> BoxedParser<'static, String> {
+    token("::=")
+        .otherwise(token("<-"))
+        .otherwise(token("="))
+        .boxed()
+}
+
+/// A backslash followed by any character
+fn escape() -> impl Parser<Output = char> {
+    ParseMatch("\\")
+        .and_then(ParseIf(|_| true))
+        .with_mapping(&|(_, c)| match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            c => c,
+        })
+}
+
+fn quoted(quote: &'static str, plain: fn(char) -> bool) -> BoxedParser<'static, String> {
+    let text = ParseMatch(quote)
+        .and_then(RepeatParser::new(escape().otherwise(ParseIf(plain))).or_default())
+        .and_then(ParseMatch(quote))
+        .with_mapping(&|((_, chars), _): ((String, Vec<char>), String)| {
+            chars.into_iter().collect()
+        });
+    lexeme(text)
+}
+
+fn literal() -> BoxedParser<'static, String> {
+    quoted("\"", |c| c != '"' && c != '\\')
+        .otherwise(quoted("'", |c| c != '\'' && c != '\\'))
+        .boxed()
+}
+
+/// A literal, or a range of characters such as `'a'..'z'`
+fn literal_or_range() -> BoxedParser<'static, Expr> {
+    fn single(s: &str) -> Option<char> {
+        let mut chars = s.chars();
+        let c = chars.next()?;
+        chars.next().is_none().then_some(c)
+    }
+
+    literal()
+        .and_then(
+            token("..")
+                .and_then(literal())
+                .combine(KeepSecondOutputOnly)
+                .optional(),
+        )
+        .with_try_mapping(&|(lo, hi): (String, Option<String>)| match hi {
+            None => Some(Expr::Literal(lo)),
+            Some(hi) => Some(Expr::Class {
+                ranges: vec![(single(&lo)?, single(&hi)?)],
+                negated: false,
+            }),
+        })
+        .boxed()
+}
+
+/// A PEG character class, such as `[a-z_]`
+fn class() -> BoxedParser<'static, Expr> {
+    fn class_char() -> impl Parser<Output = char> {
+        escape().otherwise(ParseIf(|c| c != ']' && c != '\\'))
+    }
+
+    let range = class_char()
+        .and_then(
+            ParseMatch("-")
+                .and_then(class_char())
+                .combine(KeepSecondOutputOnly)
+                .optional(),
+        )
+        .with_mapping(&|(lo, hi): (char, Option<char>)| (lo, hi.unwrap_or(lo)));
+    let class = ParseMatch("[")
+        .and_then(ParseMatch("^").optional())
+        .and_then(RepeatParser::new(range).or_default())
+        .and_then(ParseMatch("]"))
+        .with_mapping(&|(((_, negated), ranges), _): (
+            ((String, Option<String>), Vec<_>),
+            String,
+        )| {
+            Expr::Class {
+                ranges,
+                negated: negated.is_some(),
+            }
+        });
+    lexeme(class)
+}
+
+fn primary(syntax: Syntax, choice: &Rule<'static, Expr>) -> BoxedParser<'static, Expr> {
+    let group = |open, close| {
+        token(open)
+            .and_then(choice.downgrade())
+            .combine(KeepSecondOutputOnly)
+            .and_then(token(close))
+            .combine(KeepFirstOutputOnly)
+    };
+    let rule = ident()
+        .and_then(not(def_op()))
+        .combine(KeepFirstOutputOnly)
+        .with_mapping(&Expr::Rule);
+    let common = literal_or_range()
+        .otherwise(rule)
+        .otherwise(group("(", ")"));
+
+    match syntax {
+        Syntax::Peg => common
+            .otherwise(class())
+            .otherwise(token(".").value(Expr::Any))
+            .boxed(),
+        Syntax::Ebnf => common
+            .otherwise(group("[", "]").with_mapping(&|e| Expr::Optional(Box::new(e))))
+            .otherwise(group("{", "}").with_mapping(&|e| Expr::Many(Box::new(e))))
+            .boxed(),
+    }
+}
+
+fn item(syntax: Syntax, choice: &Rule<'static, Expr>) -> BoxedParser<'static, Expr> {
+    let prefix = token("&").otherwise(token("!")).optional();
+    let postfix = token("*")
+        .otherwise(token("+"))
+        .otherwise(token("?"))
+        .optional();
+    prefix
+        .and_then(primary(syntax, choice))
+        .and_then(postfix)
+        .with_mapping(
+            &|((prefix, e), postfix): ((Option<String>, Expr), Option<String>)| {
+                let e = match postfix.as_deref() {
+                    Some("*") => Expr::Many(Box::new(e)),
+                    Some("+") => Expr::Many1(Box::new(e)),
+                    Some("?") => Expr::Optional(Box::new(e)),
+                    _ => e,
+                };
+                match prefix.as_deref() {
+                    Some("&") => Expr::Peek(Box::new(e)),
+                    Some("!") => Expr::Not(Box::new(e)),
+                    _ => e,
+                }
+            },
+        )
+        .boxed()
+}
+
+/// Wrap a list in `f`, unless there is only one item
+fn flatten(first: Expr, rest: Vec<Expr>, f: fn(Vec<Expr>) -> Expr) -> Expr {
+    if rest.is_empty() {
+        return first;
+    }
+    let mut all = vec![first];
+    all.extend(rest);
+    f(all)
+}
+
+fn choice(syntax: Syntax, choice: &Rule<'static, Expr>) -> BoxedParser<'static, Expr> {
+    let sequence = || {
+        let next = token(",")
+            .optional()
+            .and_then(item(syntax, choice))
+            .combine(KeepSecondOutputOnly);
+        item(syntax, choice)
+            .and_then(RepeatParser::new(next).or_default())
+            .with_mapping(&|(first, rest)| flatten(first, rest, Expr::Sequence))
+    };
+    let next = token("|")
+        .otherwise(token("/"))
+        .and_then(sequence())
+        .combine(KeepSecondOutputOnly);
+    sequence()
+        .and_then(RepeatParser::new(next).or_default())
+        .with_mapping(&|(first, rest)| flatten(first, rest, Expr::Choice))
+        .boxed()
+}
+
+/// Parser of the text of a grammar, into its rules
+fn grammar_parser(syntax: Syntax) -> impl Parser<Output = Vec<(String, Expr)>> {
+    let expr = Rule::new();
+    expr.define(choice(syntax, &expr));
+
+    let rule = ident()
+        .and_then(def_op())
+        .combine(KeepFirstOutputOnly)
+        .and_then(expr)
+        .and_then(token(";").optional())
+        .combine(KeepFirstOutputOnly);
+    spacing()
+        .and_then(RepeatParser::new(rule))
+        .combine(KeepSecondOutputOnly)
+}
+
+#[cfg(test)]
+mod runtime_grammar_test {
+    use super::{Expr, Grammar};
+    use crate::traits::Parser;
+
+    #[test]
+    fn parser_outlives_grammar() {
+        let list = Grammar::peg("list <- '(' list* ')'")
+            .unwrap()
+            .parser("list")
+            .unwrap();
+        let (node, rest) = list.parse("(()(()))").unwrap();
+        assert_eq!((node.children.len(), rest.as_str()), (2, ""));
+    }
+
+    #[test]
+    fn ebnf_left_recursion() {
+        let grammar = Grammar::ebnf(
+            r#"
+            (* a sum of numbers, with optional signs *)
+            sum    ::= sum, "+", number | number ;
+            number ::= [ "-" ], digit, { digit } ;
+            digit  ::= '0'..'9' ;
+            "#,
+        )
+        .unwrap();
+
+        let input = "1+-23+4";
+        let sum = grammar
+            .parser("sum")
+            .unwrap()
+            .parse_complete(input)
+            .unwrap();
+        assert_eq!(sum.span, 0..7);
+        assert_eq!(sum.children[0].text(input), "1+-23");
+        assert_eq!(sum.children[1].text(input), "4");
+        assert_eq!(sum.children[0].children[1].children.len(), 2);
+        assert!(grammar.parser("sum").unwrap().parse("+1").is_err());
+    }
+
+    #[test]
+    fn peg_syntax() {
+        let grammar = Grammar::peg(
+            r#"
+            word    <- !keyword [a-zA-Z_] [a-zA-Z_0-9]*   # not a keyword
+            keyword <- ("if" / "else") ![a-z]
+            quoted  <- '"' (!'"' .)* '"'
+            "#,
+        )
+        .unwrap();
+
+        let word = grammar.parser("word").unwrap();
+        assert_eq!(word.parse("iffy x").unwrap().0.span, 0..4);
+        assert!(word.parse("if x").is_err());
+
+        let quoted = grammar.parser("quoted").unwrap();
+        assert_eq!(quoted.parse(r#""a b" c"#).unwrap().0.span, 0..5);
+        assert!(grammar.parser("missing").is_none());
+        assert_eq!(
+            grammar.definitions()[1].1,
+            Expr::Sequence(vec![
+                Expr::Choice(vec![
+                    Expr::Literal("if".to_string()),
+                    Expr::Literal("else".to_string())
+                ]),
+                Expr::Not(Box::new(Expr::Class {
+                    ranges: vec![('a', 'z')],
+                    negated: false
+                })),
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_grammars() {
+        assert!(Grammar::peg("a <- b").is_err());
+        assert!(Grammar::peg("a <- 'x'\na <- 'y'").is_err());
+        assert!(Grammar::peg("a <- ('x'").is_err());
+        assert!(Grammar::ebnf("a = 'ab'..'z' ;").is_err());
+    }
+}
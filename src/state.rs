@@ -0,0 +1,40 @@
+use std::any::{Any, TypeId};
+
+/// User state that can be threaded through parsers, see `Parser::parse_with`.
+///
+/// This is implemented for any `Clone` type. When a parser backtracks (for example, when the
+/// first branch of an `OrThenParser` fails), the state is restored to a snapshot taken before
+/// the branch was tried, so states should be cheap to clone.
+pub trait ParseState: Any {
+    /// Take a copy of the current state
+    fn snapshot(&self) -> Box<dyn Any>;
+    /// Go back to a state previously returned by `snapshot`
+    fn restore(&mut self, snapshot: Box<dyn Any>);
+    /// The state as `Any`, so that it can be downcast to its type
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Whether this is the unit state, which parsers are given when they are run without a
+    /// state (see `Parser::parse`)
+    fn is_unit(&self) -> bool {
+        Any::type_id(self) == TypeId::of::<()>()
+    }
+}
+
+impl<T> ParseState for T
+where
+    T: Any + Clone,
+{
+    fn snapshot(&self) -> Box<dyn Any> {
+        Box::new(self.clone())
+    }
+
+    fn restore(&mut self, snapshot: Box<dyn Any>) {
+        if let Ok(snapshot) = snapshot.downcast::<T>() {
+            *self = *snapshot;
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
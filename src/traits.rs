@@ -1,12 +1,27 @@
 use std::fmt::Debug;
 
 use crate::{
+    describe::Description,
+    errors::ParsingError,
+    lexer::Token,
     parsers::{
         and_p::{AndCombinator, AndThenParser, IdentityAndCombinator},
-        map_p::{MapParser, TryMapParser},
+        either_p::EitherParser,
+        iter_p::ParseIter,
+        label_p::{ContextParser, ExpectParser, LabelParser},
+        map_p::{MapParser, RecognizeParser, TokenMapParser, TryMapParser},
+        memo_p::{MemoCache, MemoParser},
+        opt_p::{OptionalParser, OrValueParser, ValueParser},
         or_p::OrThenParser,
+        profile_p::{ProfileParser, Profiler},
+        repeat_p::CollectParser,
+        rule_p::BoxedParser,
+        state_p::{StateMapParser, StateTryMapParser},
+        trace_p::TraceParser,
     },
-    type_alias::ParserRes,
+    position::Position,
+    state::ParseState,
+    type_alias::{ParserRes, TokenRes},
 };
 
 /// Parser trait
@@ -27,6 +42,64 @@ where
     /// If parsing did not suceed, then an error will be returned
     fn parse(&self, input: &str) -> ParserRes<Self::Output>;
 
+    /// Parse the input string, while passing some user state to the parser.
+    ///
+    /// By default the state is ignored. Parsers that contain other parsers must override this
+    /// to pass the state on to them, and parsers that backtrack must restore the state using
+    /// `ParseState::snapshot` and `ParseState::restore`.
+    fn parse_stateful(&self, input: &str, state: &mut dyn ParseState) -> ParserRes<Self::Output> {
+        let _ = state;
+        self.parse(input)
+    }
+
+    /// Describe the structure of this parser, see the `describe` module.
+    ///
+    /// By default, a parser is described by the name of its type. Parsers that contain other
+    /// parsers should override this to describe them.
+    fn describe(&self) -> Description {
+        let name = std::any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        Description::Predicate(name.rsplit("::").next().unwrap_or(name).to_string())
+    }
+
+    /// Parse the input string, with some mutable user state that is passed through all of the
+    /// combinators. See `with_state_mapping` to read and update the state.
+    fn parse_with<S>(&self, input: &str, state: &mut S) -> ParserRes<Self::Output>
+    where
+        S: ParseState,
+    {
+        self.parse_stateful(input, state)
+    }
+
+    /// Parse the input string, and fail if any of it is left over after parsing.
+    ///
+    /// The error will point at the first character that was not consumed. Errors that are
+    /// missing a position (see `ParsingError::locate`) are given one.
+    fn parse_complete(&self, input: &str) -> Result<Self::Output, ParsingError> {
+        let (out, rest) = self.parse(input).map_err(|e| e.locate(input))?;
+        match rest.chars().next() {
+            None => Ok(out),
+            Some(found) => Err(ParsingError::UnconsumedInput {
+                found,
+                position: Position::locate(input, &rest),
+            }),
+        }
+    }
+
+    /// Lazily parse a sequence of items, by applying this parser over and over to the rest
+    /// of the input. See `ParseIter`.
+    fn iter<'p>(&'p self, input: &str) -> ParseIter<'p, 'static, Self> {
+        ParseIter::new(self, input)
+    }
+
+    /// Like `iter`, but the user state is passed to every run of the parser, see `parse_with`
+    fn iter_with<'p, 's, S>(&'p self, input: &str, state: &'s mut S) -> ParseIter<'p, 's, Self>
+    where
+        S: ParseState,
+    {
+        ParseIter::new(self, input).with_state(state)
+    }
+
     /// Parse the output (see parse function), and if sucessful, map the parsed output
     fn parse_and_then_map<F, MappedOutput>(&self, input: &str, f: F) -> ParserRes<MappedOutput>
     where
@@ -63,7 +136,76 @@ where
         OrThenParser::from((self, other))
     }
 
-    fn with_mapping<'a, T>(self, mapping: &'a dyn Fn(Self::Output) -> T) -> MapParser<Self, T> {
+    /// Make this parser optional, if it fails `None` will be returned, and no input will be
+    /// consumed
+    fn optional(self) -> OptionalParser<Self> {
+        OptionalParser(self)
+    }
+
+    /// If this parser fails, return the default value of its output instead
+    fn or_default(self) -> OrValueParser<Self, Self::Output>
+    where
+        Self::Output: Default + Clone,
+    {
+        self.or_value(Default::default())
+    }
+
+    /// If this parser fails, return the given value instead
+    fn or_value(self, value: Self::Output) -> OrValueParser<Self, Self::Output>
+    where
+        Self::Output: Clone,
+    {
+        OrValueParser {
+            parser: self,
+            value,
+        }
+    }
+
+    /// Replace the output of this parser with a constant value
+    fn value<T>(self, value: T) -> ValueParser<Self, T>
+    where
+        T: Debug + Clone,
+    {
+        ValueParser {
+            parser: self,
+            value,
+        }
+    }
+
+    /// Make a new parser that consists of this parser OR another parser, which may have a
+    /// different output.
+    ///
+    /// The output will be `Either::Left` if this parser succeeded, and `Either::Right` if the
+    /// other one did.
+    fn or_either<P>(self, other: P) -> EitherParser<Self, P>
+    where
+        P: Parser,
+    {
+        EitherParser {
+            first_parse: self,
+            second_parse: other,
+        }
+    }
+
+    /// Parse this one or more times, collecting the outputs into any collection
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mini_parc::parsers::ParseIf;
+    /// use mini_parc::traits::Parser;
+    ///
+    /// let word = ParseIf(|c| c.is_alphabetic()).collect_into::<String>();
+    /// assert_eq!(word.parse("hi there"), Ok(("hi".to_string(), " there".to_string())));
+    /// ```
+    fn collect_into<C>(self) -> CollectParser<Self, C>
+    where
+        C: FromIterator<Self::Output>,
+    {
+        CollectParser::new(self)
+    }
+
+    fn with_mapping<'a, T>(self, mapping: &'a dyn Fn(Self::Output) -> T) -> MapParser<'a, Self, T> {
         MapParser {
             parser: self,
             mapping,
@@ -73,10 +215,175 @@ where
     fn with_try_mapping<'a, T>(
         self,
         try_map: &'a dyn Fn(Self::Output) -> Option<T>,
-    ) -> TryMapParser<Self, T> {
+    ) -> TryMapParser<'a, Self, T> {
         TryMapParser {
             parser: self,
             try_map,
         }
     }
+
+    /// Output the part of the input consumed by this parser, instead of its output
+    fn recognize(self) -> RecognizeParser<Self> {
+        RecognizeParser(self)
+    }
+
+    /// Map the output of this parser, with access to the user state (see `parse_with`)
+    fn with_state_mapping<'a, S, T>(
+        self,
+        mapping: &'a dyn Fn(Self::Output, &mut S) -> T,
+    ) -> StateMapParser<'a, Self, S, T> {
+        StateMapParser {
+            parser: self,
+            mapping,
+        }
+    }
+
+    /// Map the output of this parser, with access to the user state (see `parse_with`). If
+    /// the mapping returns `None`, the parser will fail.
+    fn with_state_try_mapping<'a, S, T>(
+        self,
+        try_map: &'a dyn Fn(Self::Output, &mut S) -> Option<T>,
+    ) -> StateTryMapParser<'a, Self, S, T> {
+        StateTryMapParser {
+            parser: self,
+            try_map,
+        }
+    }
+
+    /// If this parser fails, replace the error with `expected <label>`
+    fn label<S>(self, label: S) -> LabelParser<Self>
+    where
+        S: Into<String>,
+    {
+        LabelParser {
+            parser: self,
+            label: label.into(),
+        }
+    }
+
+    /// Like `label`, but the error remembers where it happened, and choices and repetitions do
+    /// not backtrack over it once it happens further into the input than they started, see
+    /// `ExpectParser`
+    fn expect<S>(self, label: S) -> ExpectParser<Self>
+    where
+        S: Into<String>,
+    {
+        ExpectParser {
+            parser: self,
+            label: label.into(),
+        }
+    }
+
+    /// If this parser fails, push a context frame (such as "in array element") onto the error
+    fn context<S>(self, frame: S) -> ContextParser<Self>
+    where
+        S: Into<String>,
+    {
+        ContextParser {
+            parser: self,
+            frame: frame.into(),
+        }
+    }
+
+    /// Record when this parser is entered and exited while trace mode is on, see the
+    /// `trace_p` module. Without the `trace` feature, this does nothing.
+    fn trace<S>(self, name: S) -> TraceParser<Self>
+    where
+        S: Into<String>,
+    {
+        TraceParser {
+            parser: self,
+            name: name.into(),
+        }
+    }
+
+    /// Store the results of this parser in a packrat cache, so that it is only run once
+    /// per position. Parsers memoized with the same name share their cached results.
+    fn memoize<S>(self, cache: &MemoCache, name: S) -> MemoParser<'_, Self>
+    where
+        S: Into<String>,
+    {
+        MemoParser::new(self, cache, name)
+    }
+
+    /// Measure the calls of this parser with a `Profiler`. Parsers profiled with the same
+    /// name are counted as the same rule.
+    fn profile<S>(self, profiler: &Profiler, name: S) -> ProfileParser<'_, Self>
+    where
+        S: Into<String>,
+    {
+        ProfileParser::new(self, profiler, name)
+    }
+
+    /// Put this parser behind a `Box`, so that parsers of different types can be stored
+    /// together
+    fn boxed<'a>(self) -> BoxedParser<'a, Self::Output>
+    where
+        Self: 'a,
+    {
+        BoxedParser(Box::new(self))
+    }
+}
+
+/// A parser of a stream of tokens (see the `lexer` module), where `K` is the type of the kinds
+/// of the tokens.
+///
+/// This is the version of `Parser` for tokens: a parser is given the tokens that are left, and
+/// returns its output and the tokens after the ones it consumed. `AndThenParser`,
+/// `OrThenParser`, `RepeatParser` and `OptionalParser` are parsers of tokens when the parsers
+/// inside of them are, and `lexer::token` matches a single token of some kind.
+pub trait StreamParser<K>
+where
+    Self: Sized,
+{
+    type Output: Debug;
+
+    fn parse_tokens<'t>(&self, tokens: &'t [Token<K>]) -> TokenRes<'t, K, Self::Output>;
+
+    /// Parse the tokens, and fail if any of them are left over after parsing
+    fn parse_all_tokens(&self, tokens: &[Token<K>]) -> Result<Self::Output, ParsingError>
+    where
+        K: Debug,
+    {
+        let (out, rest) = self.parse_tokens(tokens)?;
+        match rest.first() {
+            None => Ok(out),
+            Some(token) => Err(ParsingError::PatternNotFound(format!(
+                "unexpected {token} at {}",
+                token.position
+            ))),
+        }
+    }
+
+    /// Make a new parser that consists of this parser, followed by another parser
+    fn and_then<P>(self, other: P) -> AndThenParser<Self, P, IdentityAndCombinator>
+    where
+        P: StreamParser<K>,
+    {
+        AndThenParser::from((self, other))
+    }
+
+    /// Make a new parser that tries this parser, and then the other one if this one fails
+    fn otherwise<P>(self, other: P) -> OrThenParser<Self, P>
+    where
+        P: StreamParser<K, Output = Self::Output>,
+    {
+        OrThenParser::from((self, other))
+    }
+
+    /// Make this parser optional, if it fails `None` will be returned, and no tokens will be
+    /// consumed
+    fn optional(self) -> OptionalParser<Self> {
+        OptionalParser(self)
+    }
+
+    fn with_mapping<'a, T>(
+        self,
+        mapping: &'a dyn Fn(Self::Output) -> T,
+    ) -> TokenMapParser<'a, Self, Self::Output, T> {
+        TokenMapParser {
+            parser: self,
+            mapping,
+        }
+    }
 }
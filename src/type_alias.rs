@@ -1,3 +1,6 @@
-use crate::errors::ParsingError;
+use crate::{errors::ParsingError, lexer::Token};
 
 pub type ParserRes<A, E = ParsingError> = std::result::Result<(A, String), E>;
+
+/// The result of a parser of tokens, with the tokens that are left over, see `StreamParser`
+pub type TokenRes<'t, K, A, E = ParsingError> = std::result::Result<(A, &'t [Token<K>]), E>;